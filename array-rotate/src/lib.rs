@@ -10,6 +10,19 @@ fn normalize_factor(factor: i32, maximum: usize) -> usize {
 }
 
 pub fn rotate(array: &mut [i32], factor: i32) {
+    rotate_with(array, factor, |_, _| {});
+}
+
+// Same as rotate, but returns the pairs of indices it swapped, in order, so that the rotation
+// can be replayed step by step.
+pub fn trace_rotate(array: &mut [i32], factor: i32) -> Vec<(usize, usize)> {
+    let mut swaps = Vec::new();
+    rotate_with(array, factor, |i, j| swaps.push((i, j)));
+    swaps
+}
+
+// swapped is called with the indices of every pair of elements rotate swaps.
+fn rotate_with<F: FnMut(usize, usize)>(array: &mut [i32], factor: i32, mut swapped: F) {
     let reverse = factor <= 0;
     let length: usize = array.len();
     if length < 1 {
@@ -39,6 +52,7 @@ pub fn rotate(array: &mut [i32], factor: i32) {
             let b = std::mem::replace(&mut array[idx_prev], temp);
 
             array[j] = b;
+            swapped(idx_prev, j);
             j = if reverse { j + 1 } else { j - 1 };
         }
         i += 1;
@@ -46,6 +60,18 @@ pub fn rotate(array: &mut [i32], factor: i32) {
 }
 
 pub fn rotate_efficient(array: &mut [i32], factor: i32) {
+    rotate_efficient_with(array, factor, |_, _| {});
+}
+
+// Same as rotate_efficient, but returns the pairs of indices it swapped, in order.
+pub fn trace_rotate_efficient(array: &mut [i32], factor: i32) -> Vec<(usize, usize)> {
+    let mut swaps = Vec::new();
+    rotate_efficient_with(array, factor, |i, j| swaps.push((i, j)));
+    swaps
+}
+
+// swapped is called with the indices of every pair of elements rotate_efficient swaps.
+fn rotate_efficient_with<F: FnMut(usize, usize)>(array: &mut [i32], factor: i32, mut swapped: F) {
     let reverse = factor <= 0;
     let length: usize = array.len();
     if length < 1 {
@@ -53,15 +79,18 @@ pub fn rotate_efficient(array: &mut [i32], factor: i32) {
     }
 
     let f = normalize_factor(factor, length);
+    // A multiple of the length leaves the array as it is (and would make f - 1 below overflow).
+    if f == 0 {
+        return;
+    }
     let f = if reverse { f } else { length - f };
 
-    let reverse = |arr: &mut [i32], start: usize, end: usize| {
+    let mut reverse = |arr: &mut [i32], start: usize, end: usize| {
         let mut start = start;
         let mut end = end;
         while start < end {
-            let temp = arr[start];
-            arr[start] = arr[end];
-            arr[end] = temp;
+            arr.swap(start, end);
+            swapped(start, end);
 
             start += 1;
             end -= 1;
@@ -101,6 +130,50 @@ mod test_rotate {
         test_rotate_fn!(&[1], rotate_efficient, [1], 2);
     }
 
+    #[test]
+    fn test_whole_turns() {
+        for factor in [0, 6, -6, 12, -12] {
+            let mut arr = [1, 2, 3, 4, 5, 6];
+            rotate(&mut arr, factor);
+            assert_eq!([1, 2, 3, 4, 5, 6], arr, "factor {}", factor);
+
+            let mut arr = [1, 2, 3, 4, 5, 6];
+            rotate_efficient(&mut arr, factor);
+            assert_eq!([1, 2, 3, 4, 5, 6], arr, "factor {}", factor);
+        }
+    }
+
+    #[test]
+    fn test_traced_swaps_replay() {
+        for factor in -8..=8 {
+            let mut expected = [1, 2, 3, 4, 5, 6];
+            rotate(&mut expected, factor);
+
+            for trace in [trace_rotate, trace_rotate_efficient] {
+                let mut array = [1, 2, 3, 4, 5, 6];
+                let mut replayed = array;
+
+                for (i, j) in trace(&mut array, factor) {
+                    replayed.swap(i, j);
+                }
+
+                assert_eq!(expected, array, "factor {}", factor);
+                assert_eq!(expected, replayed, "factor {}", factor);
+            }
+        }
+    }
+
+    #[test]
+    fn test_traced_swap_counts() {
+        let mut array = [1, 2, 3, 4, 5, 6];
+        // Shifting by 2 moves every element one place, twice.
+        assert_eq!(10, trace_rotate(&mut array, 2).len());
+
+        let mut array = [1, 2, 3, 4, 5, 6];
+        // Reversing 4 elements, then 2, then all 6.
+        assert_eq!(2 + 1 + 3, trace_rotate_efficient(&mut array, 2).len());
+    }
+
     #[test]
     fn test_large_array() {
         let arr = [1, 2, 3, 4, 5, 6];
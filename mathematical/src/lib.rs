@@ -56,13 +56,13 @@ pub mod math {
 
         let x = gcd_loop(a, b);
 
-        return if a < T::zero() || b < T::zero() {
+        if a < T::zero() || b < T::zero() {
             let zero: T = T::zero();
             let neg_x = zero - x;
             Ok(max(x, neg_x))
         } else {
             Ok(x)
-        };
+        }
     }
 
     #[inline]
@@ -79,7 +79,11 @@ pub mod math {
 
     #[inline]
     fn max<T: PartialOrd>(a: T, b: T) -> T {
-        return if a > b { a } else { b };
+        if a > b {
+            a
+        } else {
+            b
+        }
     }
 }
 
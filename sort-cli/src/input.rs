@@ -0,0 +1,42 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+// The text of one input and the name errors refer to it by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+// Reads the files in order, or standard input if there are none.
+pub fn read(files: &[PathBuf]) -> Result<Vec<Source>, String> {
+    if files.is_empty() {
+        return Ok(vec![stdin()?]);
+    }
+    files
+        .iter()
+        .map(|path| {
+            if path.as_os_str() == "-" {
+                return stdin();
+            }
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+            Ok(Source {
+                name: path.display().to_string(),
+                text,
+            })
+        })
+        .collect()
+}
+
+fn stdin() -> Result<Source, String> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("cannot read standard input: {}", e))?;
+    Ok(Source {
+        name: "-".to_string(),
+        text,
+    })
+}
@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use crate::options::{Key, Options};
+
+// The part of a line it is sorted by. Numbers that parse as integers are kept as integers,
+// so that ones past the 53 bits of an f64's mantissa still compare exactly.
+#[derive(Debug, Clone)]
+pub enum SortKey {
+    Text(String),
+    Integer(i128),
+    Float(f64),
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Integer(a), SortKey::Integer(b)) => a.cmp(b),
+            (SortKey::Float(a), SortKey::Float(b)) => a.total_cmp(b),
+            (SortKey::Integer(a), SortKey::Float(b)) => compare_integer_float(*a, *b),
+            (SortKey::Float(a), SortKey::Integer(b)) => compare_integer_float(*b, *a).reverse(),
+            // Every key of a run comes from the same extractor, so numbers never meet text.
+            (_, SortKey::Text(_)) => Ordering::Less,
+            (SortKey::Text(_), _) => Ordering::Greater,
+        }
+    }
+}
+
+// Compares without converting integer to f64, which would round it.
+fn compare_integer_float(integer: i128, float: f64) -> Ordering {
+    // 2^127; every finite float below it in magnitude has an integer part that fits in i128.
+    const LIMIT: f64 = 170141183460469231731687303715884105728.0;
+    if float >= LIMIT {
+        return Ordering::Less;
+    }
+    if float < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    // The fraction breaks the tie: integer is below any float a little past it.
+    integer
+        .cmp(&(whole as i128))
+        .then_with(|| 0.0_f64.total_cmp(&(float - whole)))
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey {}
+
+// Finds the key of each line, with CSV column names already looked up in the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extractor {
+    key: Key,
+    numeric: bool,
+}
+
+impl Extractor {
+    pub fn new(options: &Options, header: Option<&str>) -> Result<Extractor, String> {
+        let key = match &options.key {
+            Key::Named(name) => {
+                let header = header.ok_or_else(|| format!("no header to find {} in", name))?;
+                let index = csv_fields(header)
+                    .iter()
+                    .position(|field| field == name)
+                    .ok_or_else(|| format!("no column named {}", name))?;
+                Key::Column(index + 1)
+            }
+            key => key.clone(),
+        };
+        Ok(Extractor {
+            key,
+            numeric: options.numeric,
+        })
+    }
+
+    pub fn extract(&self, line: &str) -> Result<SortKey, String> {
+        let text = match self.key {
+            Key::Line => Some(line.to_string()),
+            Key::Field {
+                index,
+                separator: Some(separator),
+            } => line.split(separator).nth(index - 1).map(String::from),
+            Key::Field {
+                index,
+                separator: None,
+            } => line.split_whitespace().nth(index - 1).map(String::from),
+            Key::Column(index) => csv_fields(line).into_iter().nth(index - 1),
+            Key::Named(_) => unreachable!("column names are resolved by Extractor::new"),
+        };
+
+        if !self.numeric {
+            // A line without the field sorts as though the field were empty.
+            return Ok(SortKey::Text(text.unwrap_or_default()));
+        }
+        let text = text.ok_or_else(|| match self.key {
+            Key::Column(index) => format!("no column {}", index),
+            _ => "no field to compare".to_string(),
+        })?;
+        let trimmed = text.trim();
+        if let Ok(integer) = trimmed.parse::<i128>() {
+            return Ok(SortKey::Integer(integer));
+        }
+        match trimmed.parse::<f64>() {
+            // Adding zero turns -0 into 0, so the two compare equal.
+            Ok(number) if !number.is_nan() => Ok(SortKey::Float(number + 0.0)),
+            _ => Err(format!("{:?} is not a number", text)),
+        }
+    }
+}
+
+// The fields of one line of CSV. Fields may be quoted, with "" standing for a quote inside
+// them; a quoted field can't span lines.
+pub fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod test_key {
+    use super::{csv_fields, Extractor, SortKey};
+    use crate::options::{Key, Options};
+
+    fn options(key: Key, numeric: bool) -> Options {
+        Options {
+            numeric,
+            reverse: false,
+            unique: false,
+            key,
+            header: false,
+            algorithm: "top-down".to_string(),
+            files: vec![],
+        }
+    }
+
+    fn extract(key: Key, numeric: bool, line: &str) -> Result<SortKey, String> {
+        Extractor::new(&options(key, numeric), None)?.extract(line)
+    }
+
+    fn text(text: &str) -> Result<SortKey, String> {
+        Ok(SortKey::Text(text.to_string()))
+    }
+
+    #[test]
+    fn test_fields() {
+        let field = |index, separator| Key::Field { index, separator };
+
+        assert_eq!(text(" b  a "), extract(Key::Line, false, " b  a "));
+        assert_eq!(text("a"), extract(field(2, None), false, " b  a "));
+        assert_eq!(text(""), extract(field(3, None), false, " b  a "));
+        assert_eq!(text(""), extract(field(2, Some(':')), false, "x::y"));
+        assert_eq!(text("y"), extract(field(3, Some(':')), false, "x::y"));
+        assert_eq!(text("b,c"), extract(Key::Column(2), false, r#"a,"b,c",d"#));
+    }
+
+    #[test]
+    fn test_numbers() {
+        let number = |line| extract(Key::Line, true, line);
+
+        assert_eq!(Ok(SortKey::Integer(-12)), number(" -12 "));
+        assert_eq!(Ok(SortKey::Float(1500.0)), number("1.5e3"));
+        assert_eq!(number("0"), number("-0"));
+        assert_eq!(number("0"), number("-0.0"));
+        assert!(SortKey::Float(-1.0) < SortKey::Float(0.5));
+        assert_eq!(Err(r#""ten" is not a number"#.to_string()), number("ten"));
+        assert_eq!(Err(r#""NaN" is not a number"#.to_string()), number("NaN"));
+        assert_eq!(
+            Err("no column 3".to_string()),
+            extract(Key::Column(3), true, "1,2")
+        );
+    }
+
+    #[test]
+    fn test_large_integers() {
+        let number = |line| extract(Key::Line, true, line).unwrap();
+
+        // 2^53 + 1 is the first integer an f64 can't hold; it parses as 2^53.
+        assert!(number("9007199254740992") < number("9007199254740993"));
+        assert!(number("9007199254740993") > number("9007199254740992.0"));
+        assert_eq!(number("9007199254740992"), number("9007199254740992.0"));
+        assert_eq!(
+            Ok(SortKey::Integer(i128::MIN)),
+            extract(Key::Line, true, &i128::MIN.to_string())
+        );
+    }
+
+    #[test]
+    fn test_integers_against_floats() {
+        let integer = SortKey::Integer;
+        let float = SortKey::Float;
+
+        assert_eq!(integer(3), float(3.0));
+        assert!(integer(3) < float(3.5));
+        assert!(integer(3) > float(2.5));
+        assert!(integer(-3) < float(-2.5));
+        assert!(integer(-3) > float(-3.5));
+        assert!(float(2.999) < integer(3));
+        assert!(integer(i128::MAX) < float(f64::INFINITY));
+        assert!(integer(i128::MAX) < float(1e39));
+        assert!(integer(i128::MIN) > float(-1e39));
+        assert_eq!(
+            integer(i128::MIN),
+            float(-170141183460469231731687303715884105728.0)
+        );
+        assert!(integer(i128::MIN) > float(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_named_columns() {
+        let options = options(Key::Named("price".to_string()), true);
+
+        let extractor = Extractor::new(&options, Some("name,price")).unwrap();
+
+        assert_eq!(Ok(SortKey::Float(2.5)), extractor.extract("tea,2.5"));
+        assert_eq!(
+            Err("no column named price".to_string()),
+            Extractor::new(&options, Some("name,cost"))
+        );
+        assert_eq!(
+            Err("no header to find price in".to_string()),
+            Extractor::new(&options, None)
+        );
+    }
+
+    #[test]
+    fn test_csv_fields() {
+        assert_eq!(vec![""], csv_fields(""));
+        assert_eq!(vec!["a", "", "c"], csv_fields("a,,c"));
+        assert_eq!(
+            vec!["say \"hi\"", "x,y", ""],
+            csv_fields(r#""say ""hi""","x,y","""#)
+        );
+        assert_eq!(vec!["a\"b"], csv_fields("a\"b"));
+    }
+}
@@ -0,0 +1,80 @@
+use std::env;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::process;
+
+mod input;
+mod key;
+mod options;
+mod order;
+
+use key::Extractor;
+use options::{Command, Options, USAGE};
+
+fn main() {
+    let options = match options::parse(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::ListAlgorithms) => {
+            print!("{}", order::list());
+            return;
+        }
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("sort-cli: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = order::resolve(&options.algorithm) {
+        eprintln!("sort-cli: {}; see --list-algorithms", e);
+        process::exit(2);
+    }
+
+    if let Err(e) = run(&options) {
+        eprintln!("sort-cli: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let sources = input::read(&options.files)?;
+
+    // Each line with where it came from, for error messages.
+    let mut lines: Vec<(&str, usize, &str)> = sources
+        .iter()
+        .flat_map(|source| {
+            let name = source.name.as_str();
+            (1..)
+                .zip(source.text.lines())
+                .map(move |(n, line)| (name, n, line))
+        })
+        .collect();
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let header = options.header.then(|| lines.remove(0).2);
+
+    let extractor = Extractor::new(options, header)?;
+    let keys = lines
+        .iter()
+        .map(|&(name, n, line)| {
+            extractor
+                .extract(line)
+                .map_err(|e| format!("{}:{}: {}", name, n, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let order = order::sort(&keys, options)?;
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let written = header
+        .into_iter()
+        .chain(order.into_iter().map(|i| lines[i].2))
+        .try_for_each(|line| writeln!(out, "{}", line))
+        .and_then(|()| out.flush());
+    match written {
+        // Whoever reads the output stopped early, as head does; that isn't an error.
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(()),
+        written => written.map_err(|e| e.to_string()),
+    }
+}
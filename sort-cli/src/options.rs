@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: sort-cli [options] [file...]
+
+Sorts the lines of the files, or of standard input if there are none or a file is -, and
+writes them to standard output. Lines with equal keys keep their input order.
+
+Options:
+  -n, --numeric              compare keys as integers or floats
+  -r, --reverse              sort in descending order
+  -u, --unique               print only the first of each run of lines with equal keys
+  -k, --key <field>          compare the 1-based field instead of the whole line
+  -t, --field-separator <c>  fields are separated by c instead of runs of whitespace
+  -c, --csv <column>         compare a CSV column, by 1-based number or header name
+      --header               keep the first line at the top; implied by a column name
+  -a, --algorithm <name>     top-down (default), bottom-up, natural, parallel, or any name
+                             from --list-algorithms
+      --list-algorithms      list the algorithms and exit
+  -h, --help                 show this message";
+
+// Which part of a line is compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Key {
+    Line,
+    // A 1-based field, split on the separator or, if there is none, on whitespace.
+    Field {
+        index: usize,
+        separator: Option<char>,
+    },
+    // A 1-based CSV column.
+    Column(usize),
+    // The CSV column the header line gives this name.
+    Named(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub numeric: bool,
+    pub reverse: bool,
+    pub unique: bool,
+    pub key: Key,
+    pub header: bool,
+    pub algorithm: String,
+    // None reads standard input, as does a path of -.
+    pub files: Vec<PathBuf>,
+}
+
+// What the command line asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run(Options),
+    ListAlgorithms,
+    Help,
+}
+
+// Parses the arguments after the program name.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut options = Options {
+        numeric: false,
+        reverse: false,
+        unique: false,
+        key: Key::Line,
+        header: false,
+        algorithm: "top-down".to_string(),
+        files: Vec::new(),
+    };
+    let mut field = None;
+    let mut separator = None;
+    let mut column = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--list-algorithms" => return Ok(Command::ListAlgorithms),
+            "--numeric" | "-n" => options.numeric = true,
+            "--reverse" | "-r" => options.reverse = true,
+            "--unique" | "-u" => options.unique = true,
+            "--header" => options.header = true,
+            "--key" | "-k" => field = Some(number(&arg, &value(&arg)?)?),
+            "--field-separator" | "-t" => {
+                let value = value(&arg)?;
+                let mut chars = value.chars();
+                separator = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => return Err(format!("{} expects one character, got {}", arg, value)),
+                }
+            }
+            "--csv" | "-c" => column = Some(value(&arg)?),
+            "--algorithm" | "-a" => options.algorithm = value(&arg)?,
+            "-" => options.files.push(PathBuf::from(arg)),
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            _ => options.files.push(PathBuf::from(arg)),
+        }
+    }
+
+    options.key = match (field, column) {
+        (Some(_), Some(_)) => return Err("--key and --csv cannot be combined".to_string()),
+        (Some(index), None) => Key::Field { index, separator },
+        (None, Some(column)) => match column.parse() {
+            Ok(index) => Key::Column(index),
+            Err(_) => {
+                options.header = true;
+                Key::Named(column)
+            }
+        },
+        (None, None) => Key::Line,
+    };
+    if let Key::Field { index: 0, .. } | Key::Column(0) = options.key {
+        return Err("fields and columns are numbered from 1".to_string());
+    }
+    if separator.is_some() && field.is_none() {
+        return Err("--field-separator needs --key".to_string());
+    }
+    Ok(Command::Run(options))
+}
+
+fn number<N: std::str::FromStr>(name: &str, value: &str) -> Result<N, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+#[cfg(test)]
+mod test_options {
+    use super::{parse, Command, Key, Options};
+    use std::path::PathBuf;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn options(line: &str) -> Options {
+        match parse(args(line)) {
+            Ok(Command::Run(options)) => options,
+            other => panic!("{}: {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(
+            Options {
+                numeric: false,
+                reverse: false,
+                unique: false,
+                key: Key::Line,
+                header: false,
+                algorithm: "top-down".to_string(),
+                files: vec![],
+            },
+            options("")
+        );
+    }
+
+    #[test]
+    fn test_all_options() {
+        assert_eq!(
+            Options {
+                numeric: true,
+                reverse: true,
+                unique: true,
+                key: Key::Field {
+                    index: 2,
+                    separator: Some(':'),
+                },
+                header: true,
+                algorithm: "quick_sort".to_string(),
+                files: vec![PathBuf::from("a.txt"), PathBuf::from("-")],
+            },
+            options("-n --reverse -u a.txt -k 2 -t : --header --algorithm quick_sort -")
+        );
+    }
+
+    #[test]
+    fn test_csv_columns() {
+        let by_number = options("--csv 3");
+        let by_name = options("-c price");
+
+        assert_eq!((Key::Column(3), false), (by_number.key, by_number.header));
+        assert_eq!(
+            (Key::Named("price".to_string()), true),
+            (by_name.key, by_name.header)
+        );
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Ok(Command::Help), parse(args("-n --help")));
+        assert_eq!(
+            Ok(Command::ListAlgorithms),
+            parse(args("--list-algorithms"))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        for (line, error) in [
+            ("--key", "--key needs a value"),
+            ("-k two", "-k expects a number, got two"),
+            ("-k 0", "fields and columns are numbered from 1"),
+            ("--csv 0", "fields and columns are numbered from 1"),
+            ("-k 1 -t ::", "-t expects one character, got ::"),
+            ("-t ,", "--field-separator needs --key"),
+            ("-k 1 --csv 2", "--key and --csv cannot be combined"),
+            ("--colour", "unknown option --colour"),
+        ] {
+            assert_eq!(Err(error.to_string()), parse(args(line)), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+
+use sorting::sorter::Registry;
+
+use crate::key::SortKey;
+use crate::options::Options;
+
+// Short names for the algorithms people reach for most, next to the registry's own names.
+const ALIASES: [(&str, &str); 4] = [
+    ("top-down", "top_down_merge_sort"),
+    ("bottom-up", "bottom_up_merge_sort"),
+    ("natural", "natural_merge_sort"),
+    ("parallel", "top_down_merge_sort_par"),
+];
+
+// What the algorithms sort: a line's key and its position in the input. Equal keys are
+// ordered by position, so that every algorithm, stable or not, gives the same output.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    key: &'a SortKey,
+    index: usize,
+    reverse: bool,
+}
+
+impl Ord for Entry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let order = self.key.cmp(other.key);
+        let order = if self.reverse { order.reverse() } else { order };
+        order.then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for Entry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry<'_> {}
+
+// The registry name an algorithm name or alias stands for.
+pub fn resolve(algorithm: &str) -> Result<&'static str, String> {
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == algorithm)
+        .map_or(algorithm, |(_, name)| name);
+    Registry::<Entry>::comparison_sorts()
+        .names()
+        .into_iter()
+        .find(|registered| *registered == name)
+        .ok_or_else(|| format!("unknown algorithm {}", algorithm))
+}
+
+// One line per algorithm, with its aliases.
+pub fn list() -> String {
+    let mut out = String::new();
+    for sorter in Registry::<Entry>::comparison_sorts().iter() {
+        out.push_str(sorter.name());
+        for (alias, _) in ALIASES.iter().filter(|(_, name)| *name == sorter.name()) {
+            out.push_str(&format!(" ({})", alias));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// The positions of keys in sorted order, sorted by the algorithm options name.
+pub fn sort(keys: &[SortKey], options: &Options) -> Result<Vec<usize>, String> {
+    let name = resolve(&options.algorithm)?;
+    let registry = Registry::comparison_sorts();
+    let sorter = registry.get(name).expect("resolved names are registered");
+
+    let mut entries: Vec<Entry> = keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| Entry {
+            key,
+            index,
+            reverse: options.reverse,
+        })
+        .collect();
+    sorter.sort(&mut entries);
+    if options.unique {
+        entries.dedup_by(|a, b| a.key == b.key);
+    }
+    Ok(entries.iter().map(|entry| entry.index).collect())
+}
+
+#[cfg(test)]
+mod test_order {
+    use super::{list, resolve, sort};
+    use crate::key::SortKey;
+    use crate::options::{Key, Options};
+
+    fn options(algorithm: &str, reverse: bool, unique: bool) -> Options {
+        Options {
+            numeric: false,
+            reverse,
+            unique,
+            key: Key::Line,
+            header: false,
+            algorithm: algorithm.to_string(),
+            files: vec![],
+        }
+    }
+
+    fn texts(texts: &[&str]) -> Vec<SortKey> {
+        texts.iter().map(|t| SortKey::Text(t.to_string())).collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(Ok("top_down_merge_sort"), resolve("top-down"));
+        assert_eq!(Ok("top_down_merge_sort_par"), resolve("parallel"));
+        assert_eq!(Ok("heap_sort"), resolve("heap_sort"));
+        assert_eq!(Err("unknown algorithm bogo".to_string()), resolve("bogo"));
+    }
+
+    #[test]
+    fn test_list() {
+        let list = list();
+
+        assert!(list.starts_with("top_down_merge_sort (top-down)\n"));
+        assert!(list.contains("\nquick_sort\n"));
+    }
+
+    #[test]
+    fn test_every_algorithm_agrees() {
+        let keys = texts(&["b", "a", "c", "a", "b", "a"]);
+
+        for algorithm in list().lines() {
+            let name = algorithm.split(' ').next().unwrap();
+
+            assert_eq!(
+                Ok(vec![1, 3, 5, 0, 4, 2]),
+                sort(&keys, &options(name, false, false)),
+                "{}",
+                name
+            );
+            assert_eq!(
+                Ok(vec![2, 0, 4, 1, 3, 5]),
+                sort(&keys, &options(name, true, false)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_unique() {
+        let keys = texts(&["b", "a", "c", "a", "b", "a"]);
+
+        assert_eq!(
+            Ok(vec![1, 0, 2]),
+            sort(&keys, &options("top-down", false, true))
+        );
+        assert_eq!(
+            Ok(vec![2, 0, 1]),
+            sort(&keys, &options("top-down", true, true))
+        );
+        assert_eq!(Ok(vec![]), sort(&[], &options("top-down", false, true)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+// Each case sorts a file from tests/data, given on standard input, and compares the output
+// with the file of the same name in tests/expected.
+const CASES: [(&str, &str, &[&str]); 12] = [
+    ("words", "words.txt", &[]),
+    ("words_reverse_unique", "words.txt", &["-r", "-u"]),
+    ("numbers", "numbers.txt", &["-n"]),
+    (
+        "numbers_reverse",
+        "numbers.txt",
+        &["--numeric", "--reverse"],
+    ),
+    ("numbers_unique", "numbers.txt", &["-n", "-u"]),
+    ("big_integers", "big_integers.txt", &["-n"]),
+    ("big_integers_unique", "big_integers.txt", &["-n", "-u"]),
+    ("people_by_age", "people.txt", &["-n", "-k", "2"]),
+    ("people_by_city", "people.txt", &["--key", "3"]),
+    (
+        "passwd_by_uid",
+        "passwd.txt",
+        &["-t", ":", "-k", "3", "-n", "-r"],
+    ),
+    ("items_by_price", "items.csv", &["--csv", "price", "-n"]),
+    (
+        "items_by_category",
+        "items.csv",
+        &["-c", "3", "--header", "-u"],
+    ),
+];
+
+fn path(dir: &str, name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(dir)
+        .join(name)
+}
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sort-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn algorithms() -> Vec<String> {
+    stdout(&run(&["--list-algorithms"], ""))
+        .lines()
+        .map(|line| line.split(' ').next().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_expected_output() {
+    let algorithms = algorithms();
+    assert_eq!(11, algorithms.len());
+
+    for (expected, input, args) in CASES {
+        let input = fs::read_to_string(path("data", input)).unwrap();
+        let expected = fs::read_to_string(path("expected", &format!("{}.txt", expected))).unwrap();
+
+        for algorithm in algorithms.iter() {
+            let mut args = args.to_vec();
+            args.extend(["--algorithm", algorithm]);
+
+            assert_eq!(expected, stdout(&run(&args, &input)), "{:?}", args);
+        }
+    }
+}
+
+#[test]
+fn test_aliases() {
+    let input = fs::read_to_string(path("data", "people.txt")).unwrap();
+    let expected = fs::read_to_string(path("expected", "people_by_age.txt")).unwrap();
+
+    for alias in ["top-down", "bottom-up", "natural", "parallel"] {
+        assert_eq!(
+            expected,
+            stdout(&run(&["-n", "-k", "2", "-a", alias], &input)),
+            "{}",
+            alias
+        );
+    }
+}
+
+#[test]
+fn test_files_and_stdin() {
+    let numbers = path("data", "numbers.txt");
+    let numbers = numbers.to_str().unwrap();
+    let input = fs::read_to_string(numbers).unwrap();
+    let expected = fs::read_to_string(path("expected", "numbers_unique.txt")).unwrap();
+
+    assert_eq!(
+        expected,
+        stdout(&run(&["-n", "-u", numbers, "-", numbers], &input))
+    );
+    assert_eq!("", stdout(&run(&["--csv", "price"], "")));
+}
+
+#[test]
+fn test_errors() {
+    let output = run(&["-n"], "1\nten\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("sort-cli: -:2: \"ten\" is not a number\n", stderr(&output));
+
+    let output = run(&["--csv", "cost"], "name,price\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("sort-cli: no column named cost\n", stderr(&output));
+
+    let output = run(&["missing.txt"], "");
+    assert_eq!(Some(1), output.status.code());
+    assert!(stderr(&output).starts_with("sort-cli: cannot read missing.txt: "));
+
+    let output = run(&["-a", "bogo"], "");
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!(
+        "sort-cli: unknown algorithm bogo; see --list-algorithms\n",
+        stderr(&output)
+    );
+
+    let output = run(&["--colour"], "");
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr(&output).starts_with("sort-cli: unknown option --colour\n\nUsage: "));
+}
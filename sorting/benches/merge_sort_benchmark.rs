@@ -1,6 +1,38 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use rayon::slice::ParallelSliceMut;
-use sorting::merge_sort::{top_down_merge_sort, top_down_merge_sort_par, bottom_up_merge_sort};
+use rayon::ThreadPoolBuilder;
+use sorting::tim_sort::tim_sort;
+use sorting::kmerge::kmerge;
+use sorting::loser_tree::loser_tree_merge;
+use sorting::quick_sort::{intro_sort, pdq_sort, quick_sort};
+use sorting::radix_sort::{radix_sort, radix_sort_par};
+use sorting::sample_sort::sample_sort_par;
+use sorting::bucket_sort::bucket_sort;
+use sorting::counting_sort::auto_counting_sort;
+use sorting::merge_sort::{in_place_merge_sort, top_down_merge_sort, top_down_merge_sort_par, bottom_up_merge_sort, natural_merge_sort, par_merge, MergeSorter, ParMergeSorter};
+
+// Deterministic pseudo-random values so runs are comparable between benchmark runs.
+fn random_input(size: usize) -> Vec<i32> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..size)
+        .map(|_| {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            (state >> 33) as i32
+        })
+        .collect()
+}
+
+// Sorted input with a short unsorted tail, like a log that had a few entries appended.
+fn nearly_sorted_input(size: usize) -> Vec<i32> {
+    let mut input: Vec<i32> = (0..size as i32).collect();
+    let tail = size / 100;
+    input[size - tail..].copy_from_slice(&random_input(tail));
+    input
+}
+
+fn sawtooth_input(size: usize) -> Vec<i32> {
+    (0..size).map(|i| (i % 1_000) as i32).collect()
+}
 
 pub fn top_down_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("top down merge sort");
@@ -8,6 +40,8 @@ pub fn top_down_benchmark(c: &mut Criterion) {
     let mut work = (vec![4; 4_000]).into_boxed_slice();
     group.bench_function("seq", |cb| cb.iter(|| top_down_merge_sort(black_box(&mut input), black_box(&mut work))));
     group.bench_function("parallel", |cb| cb.iter(|| top_down_merge_sort_par(black_box(&mut input), black_box(&mut work))));
+    let sorter = ParMergeSorter::with_num_threads(0).unwrap().sequential_cutoff(512);
+    group.bench_function("parallel (dedicated pool)", |cb| cb.iter(|| sorter.sort(black_box(&mut input), black_box(&mut work))));
     group.finish();
 }
 
@@ -15,6 +49,254 @@ pub fn bottom_up_benchmark(c: &mut Criterion) {
     let mut input = (vec![1; 4_000]).into_boxed_slice();
     let mut work = (vec![4; 4_000]).into_boxed_slice();
     c.bench_function("bottom up merge sort", |cb| cb.iter(|| bottom_up_merge_sort(black_box(&mut input), black_box(&mut work))));
+
+    // From the default runs of 32, 2^17 and 2^18 elements take 12 and 13 passes, an even and an
+    // odd number respectively. Merging from runs of one they take 17 and 18, which is how the
+    // ping-pong passes are timed against copying back after every pass.
+    let mut group = c.benchmark_group("bottom up merge sort random");
+    for size in [1 << 17, 1 << 18] {
+        let data = random_input(size);
+        let mut work = vec![0; size];
+        group.bench_with_input(BenchmarkId::new("default cutoff", size), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| bottom_up_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+        let mut sorter = MergeSorter::new().insertion_cutoff(1);
+        group.bench_with_input(BenchmarkId::new("ping-pong", size), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| sorter.sort_bottom_up(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("copy per pass", size), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| copy_per_pass_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+// The bottom-up merge sort as it was before the passes swapped input and work: every pass merges
+// into work and copies the result back into input. Kept as the baseline for the ping-pong passes.
+fn copy_per_pass_merge_sort(input: &mut [i32], work: &mut [i32]) {
+    let length = input.len();
+    let mut width = 1;
+    while width < length {
+        let mut start = 0;
+        while start < length {
+            let middle = (start + width).min(length);
+            let end = (start + 2 * width).min(length);
+            let (mut i, mut j) = (start, middle);
+            for slot in work[start..end].iter_mut() {
+                if i < middle && (j >= end || input[i] <= input[j]) {
+                    *slot = input[i];
+                    i += 1;
+                } else {
+                    *slot = input[j];
+                    j += 1;
+                }
+            }
+            start = end;
+        }
+        input.copy_from_slice(&work[..length]);
+        width *= 2;
+    }
+}
+
+pub fn natural_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("natural vs bottom up merge sort");
+    let size = 100_000;
+    let inputs = [
+        ("sorted", (0..size as i32).collect::<Vec<_>>()),
+        ("nearly sorted", nearly_sorted_input(size)),
+        ("reversed", (0..size as i32).rev().collect()),
+        ("random", random_input(size)),
+    ];
+    let mut work = vec![0; size];
+    for (name, data) in inputs.iter() {
+        group.bench_with_input(BenchmarkId::new("natural", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| natural_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("bottom up", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| bottom_up_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+// Sweeps the length below which runs are sorted directly instead of merged, 1 meaning not at
+// all, to pick DEFAULT_INSERTION_CUTOFF.
+pub fn insertion_cutoff_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insertion cutoff");
+    let data = random_input(100_000);
+    let mut work = vec![0; data.len()];
+    for cutoff in [1, 8, 16, 32, 64, 128] {
+        let mut sorter = MergeSorter::new().insertion_cutoff(cutoff);
+        group.bench_with_input(BenchmarkId::new("top down", cutoff), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| sorter.sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("bottom up", cutoff), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| sorter.sort_bottom_up(black_box(input)), BatchSize::LargeInput)
+        });
+        let par_sorter = ParMergeSorter::new().insertion_cutoff(cutoff);
+        group.bench_with_input(BenchmarkId::new("parallel", cutoff), &data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| par_sorter.sort(black_box(input), black_box(&mut work)).unwrap(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+pub fn tim_sort_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tim sort");
+    let size = 100_000;
+    let inputs = [
+        ("random", random_input(size)),
+        ("sorted", (0..size as i32).collect::<Vec<_>>()),
+        ("reversed", (0..size as i32).rev().collect()),
+        ("sawtooth", sawtooth_input(size)),
+    ];
+    let mut work = vec![0; size];
+    for (name, data) in inputs.iter() {
+        group.bench_with_input(BenchmarkId::new("tim sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| tim_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("bottom up", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| bottom_up_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("slice::sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| black_box(input).sort(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+pub fn unstable_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unstable sort");
+    let size = 100_000;
+    let inputs = [
+        ("random", random_input(size)),
+        ("few distinct", random_input(size).iter().map(|x| x % 4).collect()),
+        ("sorted", (0..size as i32).collect::<Vec<_>>()),
+        ("reversed", (0..size as i32).rev().collect()),
+        ("sawtooth", sawtooth_input(size)),
+    ];
+    for (name, data) in inputs.iter() {
+        group.bench_with_input(BenchmarkId::new("quick sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| quick_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("intro sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| intro_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("pdq sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| pdq_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("slice::sort_unstable", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| black_box(input).sort_unstable(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+pub fn radix_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("radix sort");
+    let size = 100_000;
+    let inputs = [("random", random_input(size)), ("few distinct", random_input(size).iter().map(|x| x % 4).collect())];
+    let mut work = vec![0; size];
+    for (name, data) in inputs.iter() {
+        group.bench_with_input(BenchmarkId::new("radix sort", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| radix_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("top down", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| top_down_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("slice::sort_unstable", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| black_box(input).sort_unstable(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+pub fn bounded_range_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bounded range");
+    let size = 100_000;
+    let mut work = vec![0; size];
+    // Like HTTP status codes.
+    let codes: Vec<i32> = random_input(size).iter().map(|x| 100 + x % 500).collect();
+    group.bench_function("auto counting sort", |cb| {
+        cb.iter_batched_ref(|| codes.clone(), |input| auto_counting_sort(black_box(input)), BatchSize::LargeInput)
+    });
+    group.bench_function("top down", |cb| {
+        cb.iter_batched_ref(|| codes.clone(), |input| top_down_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+    });
+    let uniform: Vec<f64> = random_input(size).iter().map(|&x| x as f64 / i32::MAX as f64).collect();
+    group.bench_function("bucket sort", |cb| {
+        cb.iter_batched_ref(|| uniform.clone(), |input| bucket_sort(black_box(input)), BatchSize::LargeInput)
+    });
+    group.bench_function("slice::sort_by total_cmp", |cb| {
+        cb.iter_batched_ref(|| uniform.clone(), |input| black_box(input).sort_by(f64::total_cmp), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+pub fn in_place_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("in place vs top down merge sort");
+    let size = 100_000;
+    let inputs = [("random", random_input(size)), ("nearly sorted", nearly_sorted_input(size))];
+    let mut work = vec![0; size];
+    for (name, data) in inputs.iter() {
+        group.bench_with_input(BenchmarkId::new("in place", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| in_place_merge_sort(black_box(input)), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("top down", name), data, |cb, data| {
+            cb.iter_batched_ref(|| data.clone(), |input| top_down_merge_sort(black_box(input), black_box(&mut work)), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+pub fn par_merge_benchmark(c: &mut Criterion) {
+    let left: Vec<i32> = (0..2_000_000).map(|i| i * 2).collect();
+    let right: Vec<i32> = (0..2_000_000).map(|i| i * 2 + 1).collect();
+    let mut out = vec![0; left.len() + right.len()];
+    c.bench_function("par merge", |cb| cb.iter(|| par_merge(black_box(&left), black_box(&right), black_box(&mut out))));
+}
+
+// The 40M-element input of test_large_array plus a random one, sorted on 1, 2, 4, ... threads
+// up to all cores.
+pub fn par_scaling_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel sort scaling");
+    group.sample_size(10);
+    let size = 40_000_000;
+    let mut large_array = vec![0; size];
+    large_array[0] = 10;
+    let inputs = [("test_large_array", large_array), ("random", random_input(size))];
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2)).take_while(|&n| n < max_threads).collect();
+    thread_counts.push(max_threads);
+    let mut work = vec![0; size];
+    for (name, data) in inputs.iter() {
+        for &threads in thread_counts.iter() {
+            let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let id = format!("{}/{} threads", name, threads);
+            group.bench_with_input(BenchmarkId::new("top down par", &id), data, |cb, data| {
+                cb.iter_batched_ref(|| data.clone(), |input| pool.install(|| top_down_merge_sort_par(black_box(input), black_box(&mut work))), BatchSize::LargeInput)
+            });
+            group.bench_with_input(BenchmarkId::new("sample sort par", &id), data, |cb, data| {
+                cb.iter_batched_ref(|| data.clone(), |input| pool.install(|| sample_sort_par(black_box(input))), BatchSize::LargeInput)
+            });
+            group.bench_with_input(BenchmarkId::new("radix sort par", &id), data, |cb, data| {
+                cb.iter_batched_ref(|| data.clone(), |input| pool.install(|| radix_sort_par(black_box(input))), BatchSize::LargeInput)
+            });
+        }
+    }
+    group.finish();
+}
+
+// 2^18 elements split into k sorted sources, merged with the binary heap and the loser tree.
+pub fn kmerge_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kmerge");
+    for k in [4, 16, 64, 256, 1024] {
+        let mut data = random_input(1 << 18);
+        let sources: Vec<Vec<i32>> = data.chunks_mut((1 << 18) / k).map(|chunk| { chunk.sort(); chunk.to_vec() }).collect();
+        group.bench_with_input(BenchmarkId::new("heap", k), &sources, |cb, sources| cb.iter(|| kmerge(black_box(sources).iter().map(|s| s.iter())).fold(0i64, |acc, &x| acc.wrapping_add(x as i64))));
+        group.bench_with_input(BenchmarkId::new("loser tree", k), &sources, |cb, sources| cb.iter(|| loser_tree_merge(black_box(sources).iter().map(|s| s.iter())).fold(0i64, |acc, &x| acc.wrapping_add(x as i64))));
+    }
+    group.finish();
 }
 
 pub fn native_benchmark(c: &mut Criterion) {
@@ -25,5 +307,5 @@ pub fn native_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, top_down_benchmark, bottom_up_benchmark, native_benchmark);
+criterion_group!(benches, top_down_benchmark, bottom_up_benchmark, natural_benchmark, insertion_cutoff_benchmark, tim_sort_benchmark, unstable_benchmark, radix_benchmark, bounded_range_benchmark, in_place_benchmark, par_merge_benchmark, par_scaling_benchmark, kmerge_benchmark, native_benchmark);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,218 @@
+use std::cmp::Ordering;
+
+// https://en.wikipedia.org/wiki/Bucket_sort
+//
+// Splits min..=max of input into as many equal-width buckets as there are elements, copies every
+// element into its bucket and sorts the buckets with insertion sort. For uniformly distributed
+// values every bucket holds about one element, so that takes O(n) expected time and n extra
+// elements of memory; values crowded into a few buckets degrade to O(n^2). Orders like
+// f64::total_cmp, so -0.0 comes before 0.0 and NaNs sort to the ends by their sign. Stable.
+// On 100k uniform f64s it takes about as long as slice::sort_by with total_cmp; the scattered
+// writes into the buckets cost as much as the comparisons they save.
+pub fn bucket_sort(input: &mut [f64]) {
+    bucket_sort_by_key(input, |&x| x);
+}
+
+// Same as bucket_sort, but sorts by the key extracted with f.
+pub fn bucket_sort_by_key<T, F>(input: &mut [T], mut f: F)
+where
+    T: Copy,
+    F: FnMut(&T) -> f64,
+{
+    let buckets = input.len();
+    if buckets < 2 {
+        return;
+    }
+
+    // The width comes from the finite keys only; infinities and NaNs go to the first or last
+    // bucket.
+    let (min, max) = input
+        .iter()
+        .map(&mut f)
+        .filter(|key| key.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), key| {
+            (min.min(key), max.max(key))
+        });
+    let scale = if max > min {
+        buckets as f64 / (max - min)
+    } else {
+        0.0
+    };
+    let mut bucket = |x: &T| {
+        let key = f(x);
+        if key.is_nan() {
+            if key.is_sign_negative() {
+                0
+            } else {
+                buckets - 1
+            }
+        } else {
+            // Float to int casts saturate, so -inf lands in 0 and inf in buckets - 1.
+            (((key - min) * scale) as usize).min(buckets - 1)
+        }
+    };
+
+    let mut starts = vec![0; buckets + 1];
+    for x in input.iter() {
+        starts[bucket(x) + 1] += 1;
+    }
+    for i in 1..=buckets {
+        starts[i] += starts[i - 1];
+    }
+
+    let work = input.to_vec();
+    let mut next = starts.clone();
+    for x in work.iter() {
+        let b = bucket(x);
+        input[next[b]] = *x;
+        next[b] += 1;
+    }
+
+    for b in 0..buckets {
+        insertion_sort(&mut input[starts[b]..starts[b + 1]], &mut |a, b| {
+            f(a).total_cmp(&f(b))
+        });
+    }
+}
+
+fn insertion_sort<T, F>(input: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 1..input.len() {
+        let mut j = i;
+        while j > 0 && compare(&input[i], &input[j - 1]) == Ordering::Less {
+            j -= 1;
+        }
+        input[j..=i].rotate_right(1);
+    }
+}
+
+#[cfg(test)]
+mod test_bucket {
+    use crate::test_util::Lcg;
+
+    fn uniform_input(length: usize) -> Vec<f64> {
+        Lcg::new(42)
+            .take(length)
+            .map(|state| (state >> 11) as f64 / (1u64 << 53) as f64)
+            .collect()
+    }
+
+    fn bits(v: &[f64]) -> Vec<u64> {
+        v.iter().map(|x| x.to_bits()).collect()
+    }
+
+    mod test_bucket_sort {
+        use super::super::bucket_sort;
+        use super::{bits, uniform_input};
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [f64; 0] = [];
+
+            bucket_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1.5];
+
+            bucket_sort(&mut src);
+
+            assert_eq!(1.5, src[0]);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [0.2, 0.1, 1.0, 0.4, 0.4, 0.3, 0.7, 0.5];
+
+            bucket_sort(&mut input);
+
+            assert_eq!(&[0.1, 0.2, 0.3, 0.4, 0.4, 0.5, 0.7, 1.0], &input);
+        }
+
+        #[test]
+        fn test_all_equal() {
+            let mut input = [2.5; 10];
+
+            bucket_sort(&mut input);
+
+            assert_eq!(&[2.5; 10], &input);
+        }
+
+        #[test]
+        fn test_special_values() {
+            let mut input = [
+                3.5,
+                f64::NAN,
+                -0.0,
+                f64::NEG_INFINITY,
+                0.0,
+                -f64::NAN,
+                f64::INFINITY,
+                -2.25,
+                f64::MAX,
+                f64::MIN,
+            ];
+            let mut expected = input;
+            expected.sort_by(f64::total_cmp);
+
+            bucket_sort(&mut input);
+
+            assert_eq!(bits(&expected), bits(&input));
+        }
+
+        #[test]
+        fn test_uniform_input() {
+            for length in [2, 3, 10, 100, 5_000] {
+                let mut input = uniform_input(length);
+                let mut expected = input.clone();
+                expected.sort_by(f64::total_cmp);
+
+                bucket_sort(&mut input);
+
+                assert_eq!(bits(&expected), bits(&input), "length {}", length);
+            }
+        }
+
+        #[test]
+        fn test_skewed_input() {
+            let mut input: Vec<f64> = uniform_input(1_000).iter().map(|x| x.powi(8)).collect();
+            let mut expected = input.clone();
+            expected.sort_by(f64::total_cmp);
+
+            bucket_sort(&mut input);
+
+            assert_eq!(bits(&expected), bits(&input));
+        }
+    }
+
+    mod test_bucket_sort_by_key {
+        use super::super::bucket_sort_by_key;
+
+        #[test]
+        fn test_f32_key() {
+            let mut input = [(0.75f32, 'c'), (0.25, 'a'), (0.5, 'b'), (0.25, 'd')];
+
+            bucket_sort_by_key(&mut input, |p| p.0 as f64);
+
+            assert_eq!(&[(0.25, 'a'), (0.25, 'd'), (0.5, 'b'), (0.75, 'c')], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input: Vec<(f64, usize)> = (0..500)
+                .map(|i| (((i * 7919) % 37) as f64 / 37.0, i))
+                .collect();
+            let mut expected = input.clone();
+            expected.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            bucket_sort_by_key(&mut input, |p| p.0);
+
+            assert_eq!(expected, input);
+        }
+    }
+}
@@ -0,0 +1,594 @@
+use std::cmp::{self, Ordering};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use crate::loser_tree::LoserTree;
+use crate::merge_sort::MergeSorter;
+
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 << 20;
+
+// Bookkeeping for every buffered record: its (start, end) range in the chunk plus the copy of
+// that range in the merge sort's work buffer.
+const RECORD_OVERHEAD: usize = 4 * std::mem::size_of::<usize>();
+
+// Most runs merged at once, and so open at once, unless set with ExternalSorter::max_fan_in.
+pub const DEFAULT_MAX_FAN_IN: usize = 64;
+
+// Smallest read buffer given to each run during a merge.
+const MIN_RUN_BUFFER: usize = 4 << 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    // Newline-delimited records. The newline is not part of the record; every record is written
+    // back followed by one, including a last record that had none.
+    Lines,
+    // Binary records of exactly this many bytes.
+    FixedWidth(usize),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExternalSortSummary {
+    pub records: usize,
+    // Number of sorted runs spilled to disk; 0 when the input fit in the memory budget.
+    pub runs: usize,
+    // Number of passes over the data merging runs; 0 when the input fit in the memory budget
+    // and more than 1 when there were more runs than could be merged at once.
+    pub merge_passes: usize,
+}
+
+// Sorts inputs larger than memory.
+// https://en.wikipedia.org/wiki/External_sorting#External_merge_sort
+//
+// Records are read in chunks that fit in the memory budget, each chunk is sorted in memory with
+// the top-down merge sort and spilled to a temporary file as a sorted run, and the runs are then
+// merged k ways into the output, in several passes if there are too many to merge at once.
+// Records are compared as byte strings unless a comparator is given. The sort is stable.
+#[derive(Debug, Clone)]
+pub struct ExternalSorter {
+    format: RecordFormat,
+    memory_budget: usize,
+    max_fan_in: usize,
+    temp_dir: Option<PathBuf>,
+}
+
+impl ExternalSorter {
+    pub fn new(format: RecordFormat) -> Self {
+        ExternalSorter {
+            format,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            max_fan_in: DEFAULT_MAX_FAN_IN,
+            temp_dir: None,
+        }
+    }
+
+    // Approximate number of bytes to hold in memory at once. A chunk always holds at least one
+    // record, however long.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    // Most runs to merge at once, which bounds the number of files open at once. The memory
+    // budget may lower it further so every run still gets a reasonable read buffer, but a merge
+    // always reads at least two runs.
+    pub fn max_fan_in(mut self, runs: usize) -> Self {
+        self.max_fan_in = runs;
+        self
+    }
+
+    // Directory for the sorted runs. Defaults to the system's temporary directory. Runs are
+    // removed once the sort finishes or fails.
+    pub fn temp_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    pub fn sort_file<P, Q>(&self, input: P, output: Q) -> io::Result<ExternalSortSummary>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let input = File::open(input)?;
+        let output = File::create(output)?;
+        self.sort(input, output)
+    }
+
+    pub fn sort<R: Read, W: Write>(&self, input: R, output: W) -> io::Result<ExternalSortSummary> {
+        self.sort_by(input, output, |a, b| a.cmp(b))
+    }
+
+    pub fn sort_by<R, W, F>(
+        &self,
+        input: R,
+        output: W,
+        mut compare: F,
+    ) -> io::Result<ExternalSortSummary>
+    where
+        R: Read,
+        W: Write,
+        F: FnMut(&[u8], &[u8]) -> Ordering,
+    {
+        if self.format == RecordFormat::FixedWidth(0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "record width must be greater than 0",
+            ));
+        }
+
+        let mut input = RecordReader::new(BufReader::new(input), self.format);
+        let mut output = BufWriter::new(output);
+        let mut summary = ExternalSortSummary::default();
+
+        let mut temp_dir = None;
+        let mut runs = Vec::new();
+        let mut chunk = Vec::new();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut sorter = MergeSorter::new();
+
+        loop {
+            chunk.clear();
+            ranges.clear();
+            let mut exhausted = false;
+            while ranges.is_empty()
+                || chunk.len() + ranges.len() * RECORD_OVERHEAD < self.memory_budget
+            {
+                match input.append_record(&mut chunk)? {
+                    Some(range) => ranges.push(range),
+                    None => {
+                        exhausted = true;
+                        break;
+                    }
+                }
+            }
+
+            sorter.sort_by(&mut ranges, |a, b| {
+                compare(&chunk[a.0..a.1], &chunk[b.0..b.1])
+            });
+            summary.records += ranges.len();
+
+            // Everything fit in memory, so there is nothing to merge.
+            if exhausted && runs.is_empty() {
+                for &(start_idx, end_idx) in &ranges {
+                    write_record(&mut output, self.format, &chunk[start_idx..end_idx])?;
+                }
+                break;
+            }
+
+            if !ranges.is_empty() {
+                let dir = match &mut temp_dir {
+                    Some(dir) => dir,
+                    None => temp_dir.insert(self.create_temp_dir()?),
+                };
+                let path = dir.path().join(format!("run-{}", runs.len()));
+                let mut run = BufWriter::new(File::create(&path)?);
+                for &(start_idx, end_idx) in &ranges {
+                    write_record(&mut run, self.format, &chunk[start_idx..end_idx])?;
+                }
+                run.flush()?;
+                runs.push(path);
+            }
+
+            if exhausted {
+                // Free the chunk before the merge allocates its read buffers.
+                drop(chunk);
+                drop(ranges);
+                summary.runs = runs.len();
+                let dir = temp_dir.as_ref().expect("spilled runs have a directory");
+                summary.merge_passes =
+                    self.merge_runs(runs, dir.path(), &mut output, &mut compare)?;
+                break;
+            }
+        }
+
+        output.flush()?;
+        Ok(summary)
+    }
+
+    fn create_temp_dir(&self) -> io::Result<tempfile::TempDir> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("external-sort-");
+        match &self.temp_dir {
+            Some(dir) => builder.tempdir_in(dir),
+            None => builder.tempdir(),
+        }
+    }
+
+    // Runs merged at once: at most max_fan_in, and few enough that each of them and the output
+    // get MIN_RUN_BUFFER of the memory budget, but always at least two.
+    fn fan_in(&self) -> usize {
+        let affordable = (self.memory_budget / MIN_RUN_BUFFER).saturating_sub(1);
+        cmp::max(cmp::min(self.max_fan_in, affordable), 2)
+    }
+
+    // Merges the sorted runs into output, first merging consecutive groups of fan_in runs into
+    // longer runs in dir until no more than fan_in are left. Returns the number of passes.
+    fn merge_runs<W, F>(
+        &self,
+        mut runs: Vec<PathBuf>,
+        dir: &Path,
+        output: &mut W,
+        compare: &mut F,
+    ) -> io::Result<usize>
+    where
+        W: Write,
+        F: FnMut(&[u8], &[u8]) -> Ordering,
+    {
+        let fan_in = self.fan_in();
+        let buffer_size = cmp::max(self.memory_budget / (fan_in + 1), MIN_RUN_BUFFER);
+        let mut next_run = runs.len();
+        let mut passes = 1;
+
+        while runs.len() > fan_in {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(fan_in));
+            for group in runs.chunks(fan_in) {
+                if let [run] = group {
+                    merged.push(run.clone());
+                    continue;
+                }
+                let path = dir.join(format!("run-{}", next_run));
+                next_run += 1;
+                let mut run = BufWriter::with_capacity(buffer_size, File::create(&path)?);
+                self.merge_group(group, buffer_size, &mut run, compare)?;
+                run.flush()?;
+                // Free the disk space of the merged runs as soon as they are no longer needed.
+                for path in group {
+                    fs::remove_file(path)?;
+                }
+                merged.push(path);
+            }
+            runs = merged;
+            passes += 1;
+        }
+
+        self.merge_group(&runs, buffer_size, output, compare)?;
+        Ok(passes)
+    }
+
+    // k-way merge of the sorted runs with a loser tree of their heads. Ties go to the run with
+    // the lower index, which holds records from earlier in the input.
+    fn merge_group<W, F>(
+        &self,
+        runs: &[PathBuf],
+        buffer_size: usize,
+        output: &mut W,
+        compare: &mut F,
+    ) -> io::Result<()>
+    where
+        W: Write,
+        F: FnMut(&[u8], &[u8]) -> Ordering,
+    {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heads = Vec::with_capacity(runs.len());
+        for path in runs {
+            let file = File::open(path)?;
+            let mut reader =
+                RecordReader::new(BufReader::with_capacity(buffer_size, file), self.format);
+            let mut head = Vec::new();
+            heads.push(reader.read_record(&mut head)?.then_some(head));
+            readers.push(reader);
+        }
+
+        let mut tree = LoserTree::new(heads, |a: &Vec<u8>, b: &Vec<u8>| compare(a, b));
+        // The buffer of the record last written, reused for the next record read.
+        let mut spare = Vec::new();
+        while let Some(run) = tree.winner() {
+            let next = readers[run]
+                .read_record(&mut spare)?
+                .then(|| mem::take(&mut spare));
+            let head = tree.replace_winner(next).expect("the winner has a head");
+            write_record(output, self.format, &head)?;
+            spare = head;
+        }
+
+        Ok(())
+    }
+}
+
+struct RecordReader<R> {
+    reader: R,
+    format: RecordFormat,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    fn new(reader: R, format: RecordFormat) -> Self {
+        RecordReader { reader, format }
+    }
+
+    // Appends the next record to buf and returns its range, or None at the end of the input.
+    fn append_record(&mut self, buf: &mut Vec<u8>) -> io::Result<Option<(usize, usize)>> {
+        let start_idx = buf.len();
+        match self.format {
+            RecordFormat::Lines => {
+                if self.reader.read_until(b'\n', buf)? == 0 {
+                    return Ok(None);
+                }
+                if buf.last() == Some(&b'\n') {
+                    buf.pop();
+                }
+            }
+            RecordFormat::FixedWidth(width) => {
+                buf.resize(start_idx + width, 0);
+                let filled = read_full(&mut self.reader, &mut buf[start_idx..])?;
+                if filled < width {
+                    buf.truncate(start_idx);
+                    if filled == 0 {
+                        return Ok(None);
+                    }
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "input ends with a partial record of {} bytes, expected {}",
+                            filled, width
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(Some((start_idx, buf.len())))
+    }
+
+    // Replaces the contents of record with the next record. Returns false at the end of the input.
+    fn read_record(&mut self, record: &mut Vec<u8>) -> io::Result<bool> {
+        record.clear();
+        Ok(self.append_record(record)?.is_some())
+    }
+}
+
+// Like Read::read_exact, but returns how much was read when the input ends early.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn write_record<W: Write>(output: &mut W, format: RecordFormat, record: &[u8]) -> io::Result<()> {
+    output.write_all(record)?;
+    if format == RecordFormat::Lines {
+        output.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_external_sort {
+    use super::{ExternalSortSummary, ExternalSorter, RecordFormat, DEFAULT_MAX_FAN_IN};
+    use crate::test_util::random;
+
+    fn lines(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .map(|v| format!("{}\n", v))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    mod test_lines {
+        use super::*;
+
+        #[test]
+        fn test_empty_input() {
+            let mut output = Vec::new();
+
+            let summary = ExternalSorter::new(RecordFormat::Lines)
+                .sort(&b""[..], &mut output)
+                .unwrap();
+
+            assert!(output.is_empty());
+            assert_eq!(
+                ExternalSortSummary {
+                    records: 0,
+                    runs: 0,
+                    merge_passes: 0
+                },
+                summary
+            );
+        }
+
+        #[test]
+        fn test_fits_in_memory() {
+            let mut output = Vec::new();
+
+            let summary = ExternalSorter::new(RecordFormat::Lines)
+                .sort(&b"pear\napple\nfig\n"[..], &mut output)
+                .unwrap();
+
+            assert_eq!(b"apple\nfig\npear\n", &output[..]);
+            assert_eq!(
+                ExternalSortSummary {
+                    records: 3,
+                    runs: 0,
+                    merge_passes: 0
+                },
+                summary
+            );
+        }
+
+        #[test]
+        fn test_missing_final_newline() {
+            let mut output = Vec::new();
+
+            ExternalSorter::new(RecordFormat::Lines)
+                .sort(&b"b\n\na"[..], &mut output)
+                .unwrap();
+
+            assert_eq!(b"\na\nb\n", &output[..]);
+        }
+
+        #[test]
+        fn test_spills_runs() {
+            let dir = tempfile::tempdir().unwrap();
+            let values: Vec<u32> = random(2_000, 1);
+            let mut expected: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            expected.sort();
+            let mut output = Vec::new();
+
+            let summary = ExternalSorter::new(RecordFormat::Lines)
+                .memory_budget(1_000)
+                .temp_dir(dir.path())
+                .sort(&lines(&values)[..], &mut output)
+                .unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(expected, output.lines().collect::<Vec<_>>());
+            assert_eq!(2_000, summary.records);
+            assert!(summary.runs > 10, "only {} runs", summary.runs);
+            // Runs are cleaned up.
+            assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+        }
+
+        #[test]
+        fn test_more_runs_than_fan_in() {
+            let dir = tempfile::tempdir().unwrap();
+            let values: Vec<u32> = random(20_000, 4);
+            let mut expected: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            expected.sort();
+            let mut output = Vec::new();
+
+            // A budget of 5 run buffers, so the fan-in stays at 4. With the bookkeeping, 20000
+            // records of about 8 bytes spill about 40 runs, merged into 10, then 3, then the
+            // output.
+            let summary = ExternalSorter::new(RecordFormat::Lines)
+                .memory_budget(5 * (4 << 10))
+                .max_fan_in(4)
+                .temp_dir(dir.path())
+                .sort(&lines(&values)[..], &mut output)
+                .unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(expected, output.lines().collect::<Vec<_>>());
+            assert!((17..=64).contains(&summary.runs), "{} runs", summary.runs);
+            assert_eq!(3, summary.merge_passes);
+            assert_eq!(0, std::fs::read_dir(dir.path()).unwrap().count());
+        }
+
+        #[test]
+        fn test_fan_in() {
+            let sorter = ExternalSorter::new(RecordFormat::Lines);
+
+            assert_eq!(DEFAULT_MAX_FAN_IN, sorter.clone().fan_in());
+            // Each run and the output get at least 4 KiB of the budget.
+            assert_eq!(15, sorter.clone().memory_budget(64 << 10).fan_in());
+            assert_eq!(2, sorter.clone().memory_budget(100).fan_in());
+            assert_eq!(2, sorter.max_fan_in(0).fan_in());
+        }
+
+        #[test]
+        fn test_single_record_chunks() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut output = Vec::new();
+
+            let summary = ExternalSorter::new(RecordFormat::Lines)
+                .memory_budget(0)
+                .temp_dir(dir.path())
+                .sort(&b"3\n1\n2\n"[..], &mut output)
+                .unwrap();
+
+            assert_eq!(b"1\n2\n3\n", &output[..]);
+            assert_eq!(3, summary.runs);
+        }
+
+        #[test]
+        fn test_sort_by_is_stable() {
+            let dir = tempfile::tempdir().unwrap();
+            let input: String = random(500, 2)
+                .iter()
+                .enumerate()
+                .map(|(i, v)| format!("{} {}\n", v % 5, i))
+                .collect();
+            let mut expected: Vec<&str> = input.lines().collect();
+            expected.sort_by_key(|line| std::cmp::Reverse(line.as_bytes()[0]));
+            let mut output = Vec::new();
+
+            ExternalSorter::new(RecordFormat::Lines)
+                .memory_budget(300)
+                .temp_dir(dir.path())
+                .sort_by(input.as_bytes(), &mut output, |a, b| b[0].cmp(&a[0]))
+                .unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            assert_eq!(expected, output.lines().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_sort_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let input = dir.path().join("input.txt");
+            let output = dir.path().join("output.txt");
+            std::fs::write(&input, "delta\nalpha\ncharlie\nbravo\n").unwrap();
+
+            ExternalSorter::new(RecordFormat::Lines)
+                .memory_budget(64)
+                .temp_dir(dir.path())
+                .sort_file(&input, &output)
+                .unwrap();
+
+            assert_eq!(
+                "alpha\nbravo\ncharlie\ndelta\n",
+                std::fs::read_to_string(&output).unwrap()
+            );
+        }
+    }
+
+    mod test_fixed_width {
+        use super::*;
+
+        #[test]
+        fn test_spills_runs() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut values: Vec<u32> = random(3_000, 3);
+            let input: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+            values.sort();
+            let mut output = Vec::new();
+
+            let summary = ExternalSorter::new(RecordFormat::FixedWidth(4))
+                .memory_budget(2_048)
+                .temp_dir(dir.path())
+                .sort(&input[..], &mut output)
+                .unwrap();
+
+            let sorted: Vec<u32> = output
+                .chunks(4)
+                .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                .collect();
+            assert_eq!(values, sorted);
+            assert_eq!(3_000, summary.records);
+            assert!(summary.runs > 1);
+        }
+
+        #[test]
+        fn test_partial_record() {
+            let mut output = Vec::new();
+
+            let error = ExternalSorter::new(RecordFormat::FixedWidth(4))
+                .sort(&[0u8, 0, 0, 1, 0, 0][..], &mut output)
+                .unwrap_err();
+
+            assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+            assert_eq!(
+                "input ends with a partial record of 2 bytes, expected 4",
+                error.to_string()
+            );
+        }
+
+        #[test]
+        fn test_zero_width() {
+            let mut output = Vec::new();
+
+            let error = ExternalSorter::new(RecordFormat::FixedWidth(0))
+                .sort(&[1u8][..], &mut output)
+                .unwrap_err();
+
+            assert_eq!(std::io::ErrorKind::InvalidInput, error.kind());
+        }
+    }
+}
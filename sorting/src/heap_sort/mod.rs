@@ -0,0 +1,684 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// Heapsort: turn input into a binary max-heap, then repeatedly swap the root behind the heap.
+// https://en.wikipedia.org/wiki/Heapsort
+//
+// In place, O(n log n) in the worst case and not stable.
+pub fn heap_sort<T: Ord>(input: &mut [T]) {
+    heap_sort_by(input, T::cmp);
+}
+
+// Same as heap_sort, but orders elements using the comparator function.
+pub fn heap_sort_by<T, F>(input: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for pos in (0..input.len() / 2).rev() {
+        sift_down_max(input, pos, &mut compare);
+    }
+    for end in (1..input.len()).rev() {
+        input.swap(0, end);
+        sift_down_max(&mut input[..end], 0, &mut compare);
+    }
+}
+
+// Same as heap_sort, but orders elements by the key extracted with f.
+pub fn heap_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    heap_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Moves input[pos] down the max-heap in input until no child is greater.
+fn sift_down_max<T, F>(input: &mut [T], mut pos: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    loop {
+        let left = 2 * pos + 1;
+        if left >= input.len() {
+            return;
+        }
+        let right = left + 1;
+        let child = if right < input.len() && compare(&input[left], &input[right]) == Ordering::Less
+        {
+            right
+        } else {
+            left
+        };
+        if compare(&input[pos], &input[child]) != Ordering::Less {
+            return;
+        }
+        input.swap(pos, child);
+        pos = child;
+    }
+}
+
+// The smallest k items in ascending order. Keeps a heap of the k smallest items seen so far,
+// so it takes O(n log k) time and O(k) memory. Equal items may come out in any order.
+pub fn top_k<I>(items: I, k: usize) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    top_k_by(items, k, I::Item::cmp)
+}
+
+// Same as top_k, but orders items using the comparator function.
+pub fn top_k_by<I, F>(items: I, k: usize, mut compare: F) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // Reversed, so the root is the largest of the items kept.
+    let mut heap = BinaryHeap::with_comparator(|a: &I::Item, b: &I::Item| compare(b, a));
+    for item in items {
+        if heap.len() < k {
+            heap.push(item);
+        } else {
+            heap.push_pop(item);
+        }
+    }
+
+    let mut smallest = heap.into_sorted_vec();
+    smallest.reverse();
+    smallest
+}
+
+// Orders the items of a DaryHeap. Implemented for every FnMut(&T, &T) -> Ordering, so a
+// closure works as well as a type that needs to name its comparator.
+pub trait Compare<T> {
+    fn compare(&mut self, a: &T, b: &T) -> Ordering;
+}
+
+impl<T, F> Compare<T> for F
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn compare(&mut self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+// Refers to an item in a DaryHeap for decrease_key, update and remove. A handle stays valid
+// until its item leaves the heap; after that it may be reused for a later item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle(usize);
+
+// Index in positions of a handle that no item uses.
+const VACANT: usize = usize::MAX;
+
+// Min-heap in which every node has D children. The smallest item according to the comparator
+// is at the top; use a reversed comparator or std::cmp::Reverse for a max-heap.
+// https://en.wikipedia.org/wiki/D-ary_heap
+//
+// A higher D makes the tree shallower, so push and decrease_key do fewer comparisons, while pop
+// compares against D children on every level. D = 4 is often faster than a binary heap when
+// keys are decreased frequently, as in Dijkstra's algorithm.
+//
+// Items with equal keys come out in an unspecified order.
+#[derive(Clone)]
+pub struct DaryHeap<T, const D: usize, C = fn(&T, &T) -> Ordering> {
+    // Items in heap order, each with the slot of its handle.
+    items: Vec<(T, usize)>,
+    // positions[slot] is the index in items of the item with that handle, or VACANT.
+    positions: Vec<usize>,
+    free_slots: Vec<usize>,
+    compare: C,
+}
+
+pub type BinaryHeap<T, C = fn(&T, &T) -> Ordering> = DaryHeap<T, 2, C>;
+
+impl<T: Ord, const D: usize> DaryHeap<T, D> {
+    pub fn new() -> Self {
+        DaryHeap::with_comparator(T::cmp)
+    }
+
+    // Builds a heap from items in O(n) time. Handles for the items are not returned; push them
+    // one at a time when they are needed.
+    pub fn from_vec(items: Vec<T>) -> Self {
+        DaryHeap::from_vec_by(items, T::cmp)
+    }
+}
+
+impl<T: Ord, const D: usize> Default for DaryHeap<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const D: usize, C: Compare<T>> DaryHeap<T, D, C> {
+    pub fn with_comparator(compare: C) -> Self {
+        const { assert!(D >= 2, "a heap node needs at least 2 children") };
+        DaryHeap {
+            items: Vec::new(),
+            positions: Vec::new(),
+            free_slots: Vec::new(),
+            compare,
+        }
+    }
+
+    // Same as from_vec, but orders items using the comparator.
+    pub fn from_vec_by(items: Vec<T>, compare: C) -> Self {
+        let mut heap = DaryHeap::with_comparator(compare);
+        heap.positions = (0..items.len()).collect();
+        heap.items = items.into_iter().enumerate().map(|(i, x)| (x, i)).collect();
+        heap.heapify();
+        heap
+    }
+
+    // Restores the heap order of all items, bottom up.
+    fn heapify(&mut self) {
+        for pos in (0..self.items.len().div_ceil(D)).rev() {
+            self.sift_down(pos);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // The smallest item.
+    pub fn peek(&self) -> Option<&T> {
+        self.items.first().map(|(item, _)| item)
+    }
+
+    pub fn push(&mut self, item: T) -> Handle {
+        let pos = self.items.len();
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.positions[slot] = pos;
+                slot
+            }
+            None => {
+                self.positions.push(pos);
+                self.positions.len() - 1
+            }
+        };
+        self.items.push((item, slot));
+        self.sift_up(pos);
+
+        Handle(slot)
+    }
+
+    // Removes and returns the smallest item.
+    pub fn pop(&mut self) -> Option<T> {
+        self.remove_at(0)
+    }
+
+    // Replaces the smallest item with item, which takes over its handle, and returns the old
+    // smallest item. Cheaper than a pop followed by a push. On an empty heap, item is pushed.
+    pub fn replace_top(&mut self, item: T) -> Option<T> {
+        if self.items.is_empty() {
+            self.push(item);
+            return None;
+        }
+
+        let top = std::mem::replace(&mut self.items[0].0, item);
+        self.sift_down(0);
+        Some(top)
+    }
+
+    // Pushes item and pops the smallest item, which may be item itself. Cheaper than a push
+    // followed by a pop.
+    pub fn push_pop(&mut self, item: T) -> T {
+        match self.items.first() {
+            Some((top, _)) if self.compare.compare(top, &item) == Ordering::Less => {
+                self.replace_top(item).expect("the heap is not empty")
+            }
+            _ => item,
+        }
+    }
+
+    // The item with the handle, or None if it has left the heap.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let pos = *self.positions.get(handle.0)?;
+        self.items.get(pos).map(|(item, _)| item)
+    }
+
+    // Replaces the item with the handle by a smaller or equal one.
+    //
+    // Panics if the handle's item has left the heap or if item is greater than the current one.
+    pub fn decrease_key(&mut self, handle: Handle, item: T) {
+        let pos = self.position(handle);
+        assert!(
+            self.compare.compare(&item, &self.items[pos].0) != Ordering::Greater,
+            "decrease_key with a greater item"
+        );
+        self.items[pos].0 = item;
+        self.sift_up(pos);
+    }
+
+    // Replaces the item with the handle and returns the old one. The new item may be smaller or
+    // greater.
+    //
+    // Panics if the handle's item has left the heap.
+    pub fn update(&mut self, handle: Handle, item: T) -> T {
+        let pos = self.position(handle);
+        let old = std::mem::replace(&mut self.items[pos].0, item);
+        self.restore(pos);
+        old
+    }
+
+    // Removes and returns the item with the handle.
+    //
+    // Panics if the handle's item has left the heap.
+    pub fn remove(&mut self, handle: Handle) -> T {
+        let pos = self.position(handle);
+        self.remove_at(pos).expect("the position holds an item")
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items.into_iter().map(|(item, _)| item).collect()
+    }
+
+    // All items, smallest first.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.items.len());
+        while let Some(item) = self.pop() {
+            sorted.push(item);
+        }
+        sorted
+    }
+
+    fn position(&self, handle: Handle) -> usize {
+        match self.positions.get(handle.0) {
+            Some(&pos) if pos != VACANT => pos,
+            _ => panic!("the handle's item is not in the heap"),
+        }
+    }
+
+    fn remove_at(&mut self, pos: usize) -> Option<T> {
+        let last = self.items.len().checked_sub(1)?;
+        if pos > last {
+            return None;
+        }
+
+        self.swap(pos, last);
+        let (item, slot) = self.items.pop().expect("the heap is not empty");
+        self.positions[slot] = VACANT;
+        self.free_slots.push(slot);
+        if pos < self.items.len() {
+            self.restore(pos);
+        }
+
+        Some(item)
+    }
+
+    fn less(&mut self, a: usize, b: usize) -> bool {
+        self.compare.compare(&self.items[a].0, &self.items[b].0) == Ordering::Less
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        self.positions[self.items[a].1] = a;
+        self.positions[self.items[b].1] = b;
+    }
+
+    // Moves the item at pos up or down, whichever its new key needs.
+    fn restore(&mut self, pos: usize) {
+        if pos > 0 && self.less(pos, (pos - 1) / D) {
+            self.sift_up(pos);
+        } else {
+            self.sift_down(pos);
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / D;
+            if !self.less(pos, parent) {
+                return;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let first_child = D * pos + 1;
+            if first_child >= self.items.len() {
+                return;
+            }
+            // With all D children present the loop has a constant trip count, which the
+            // compiler unrolls for small D.
+            let mut child = first_child;
+            if first_child + D <= self.items.len() {
+                for offset in 1..D {
+                    if self.less(first_child + offset, child) {
+                        child = first_child + offset;
+                    }
+                }
+            } else {
+                for other in first_child + 1..self.items.len() {
+                    if self.less(other, child) {
+                        child = other;
+                    }
+                }
+            }
+            if !self.less(child, pos) {
+                return;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+}
+
+impl<T: fmt::Debug, const D: usize, C> fmt::Debug for DaryHeap<T, D, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DaryHeap")
+            .field("items", &self.items)
+            .field("positions", &self.positions)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_heap_sort {
+    mod test_sort {
+        use super::super::heap_sort;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            heap_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            heap_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_small_arrays() {
+            let mut input = [2, 1];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[1, 2], &input);
+
+            let mut input = [4, 1, 2];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[1, 2, 4], &input);
+
+            let mut input = [4, 1, 2, 5];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[1, 2, 4, 5], &input);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+        }
+
+        #[test]
+        fn test_u64_array() {
+            let mut input = [u64::MAX, 7, 0, 42, 7, 1 << 40];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[0, 7, 7, 42, 1 << 40, u64::MAX], &input);
+        }
+
+        #[test]
+        fn test_tuple_array() {
+            let mut input = [(2u32, 1u32), (1, 9), (2, 0), (1, 3), (0, 5)];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&[(0, 5), (1, 3), (1, 9), (2, 0), (2, 1)], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+
+            heap_sort(&mut input);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_every_length() {
+            for length in 0..300u64 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 37).collect();
+                let mut expected = input.clone();
+                expected.sort();
+
+                heap_sort(&mut input);
+
+                assert_eq!(expected, input, "length {}", length);
+            }
+        }
+    }
+
+    mod test_sort_by {
+        use super::super::{heap_sort_by, heap_sort_by_key};
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            heap_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_by_key() {
+            let mut input = [(2u32, 'b'), (1, 'a'), (3, 'c'), (0, 'd')];
+
+            heap_sort_by_key(&mut input, |p| p.0);
+
+            assert_eq!(&[(0, 'd'), (1, 'a'), (2, 'b'), (3, 'c')], &input);
+        }
+    }
+
+    mod test_dary_heap {
+        use super::super::{BinaryHeap, DaryHeap};
+        use crate::test_util::random_below;
+
+        fn pop_all<const D: usize>(heap: &mut DaryHeap<u32, D>) -> Vec<u32> {
+            std::iter::from_fn(|| heap.pop()).collect()
+        }
+
+        #[test]
+        fn test_empty() {
+            let mut heap: BinaryHeap<i32> = BinaryHeap::new();
+
+            assert!(heap.is_empty());
+            assert_eq!(None, heap.peek());
+            assert_eq!(None, heap.pop());
+        }
+
+        #[test]
+        fn test_push_pop() {
+            let input = random_below(1_000, 1_000, 42);
+            let mut expected = input.clone();
+            expected.sort();
+
+            let mut binary: DaryHeap<u32, 2> = DaryHeap::new();
+            let mut ternary: DaryHeap<u32, 3> = DaryHeap::new();
+            let mut eight: DaryHeap<u32, 8> = DaryHeap::new();
+            for &x in &input {
+                binary.push(x);
+                ternary.push(x);
+                eight.push(x);
+            }
+
+            assert_eq!(1_000, binary.len());
+            assert_eq!(Some(&expected[0]), binary.peek());
+            assert_eq!(expected, pop_all(&mut binary));
+            assert_eq!(expected, pop_all(&mut ternary));
+            assert_eq!(expected, pop_all(&mut eight));
+        }
+
+        #[test]
+        fn test_from_vec() {
+            for length in 0..50 {
+                let input = random_below(length, 1_000, 42);
+                let mut expected = input.clone();
+                expected.sort();
+
+                let mut heap: DaryHeap<u32, 4> = DaryHeap::from_vec(input);
+
+                assert_eq!(expected, pop_all(&mut heap), "length {}", length);
+            }
+        }
+
+        #[test]
+        fn test_into_sorted_vec() {
+            let heap: BinaryHeap<u32> = BinaryHeap::from_vec(vec![5, 3, 9, 1, 3]);
+
+            assert_eq!(vec![1, 3, 3, 5, 9], heap.into_sorted_vec());
+        }
+
+        #[test]
+        fn test_max_heap() {
+            let mut heap = BinaryHeap::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+            for x in [3, 8, 1, 9, 4] {
+                heap.push(x);
+            }
+
+            assert_eq!(vec![9, 8, 4, 3, 1], heap.into_sorted_vec());
+        }
+
+        #[test]
+        fn test_decrease_key() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::new();
+            let handles: Vec<_> = (10..20).map(|x| heap.push(x)).collect();
+
+            heap.decrease_key(handles[7], 3);
+            heap.decrease_key(handles[2], 5);
+
+            assert_eq!(Some(&3), heap.get(handles[7]));
+            assert_eq!(Some(3), heap.pop());
+            assert_eq!(None, heap.get(handles[7]));
+            assert_eq!(Some(5), heap.pop());
+            assert_eq!(Some(10), heap.pop());
+        }
+
+        #[test]
+        #[should_panic(expected = "decrease_key with a greater item")]
+        fn test_decrease_key_greater() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::new();
+            let handle = heap.push(4);
+
+            heap.decrease_key(handle, 5);
+        }
+
+        #[test]
+        fn test_update_and_remove() {
+            let mut heap: DaryHeap<u32, 3> = DaryHeap::new();
+            let handles: Vec<_> = (0..20).map(|x| heap.push(x)).collect();
+
+            assert_eq!(0, heap.update(handles[0], 100));
+            assert_eq!(15, heap.update(handles[15], 1));
+            assert_eq!(7, heap.remove(handles[7]));
+            assert_eq!(1, heap.remove(handles[15]));
+
+            let mut expected: Vec<u32> = (1..20).filter(|&x| x != 7 && x != 15).collect();
+            expected.push(100);
+            assert_eq!(expected, heap.into_sorted_vec());
+        }
+
+        #[test]
+        #[should_panic(expected = "the handle's item is not in the heap")]
+        fn test_removed_handle() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::new();
+            let handle = heap.push(4);
+            heap.pop();
+
+            heap.remove(handle);
+        }
+
+        #[test]
+        fn test_handles_are_reused() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::new();
+            let first = heap.push(1);
+            heap.pop();
+            let second = heap.push(2);
+
+            assert_eq!(first, second);
+            assert_eq!(Some(&2), heap.get(second));
+        }
+
+        #[test]
+        fn test_replace_top() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::new();
+
+            assert_eq!(None, heap.replace_top(5));
+            heap.push(2);
+            assert_eq!(Some(2), heap.replace_top(9));
+            assert_eq!(vec![5, 9], heap.into_sorted_vec());
+        }
+
+        #[test]
+        fn test_push_pop_combined() {
+            let mut heap: BinaryHeap<u32> = BinaryHeap::from_vec(vec![4, 6]);
+
+            assert_eq!(1, heap.push_pop(1));
+            assert_eq!(4, heap.push_pop(5));
+            assert_eq!(vec![5, 6], heap.into_sorted_vec());
+        }
+    }
+
+    mod test_top_k {
+        use super::super::{top_k, top_k_by};
+
+        #[test]
+        fn test_top_k() {
+            let input = [9, 4, 7, 1, 8, 2, 2, 6];
+
+            assert_eq!(Vec::<i32>::new(), top_k(input, 0));
+            assert_eq!(vec![1, 2, 2], top_k(input, 3));
+            assert_eq!(vec![1, 2, 2, 4, 6, 7, 8, 9], top_k(input, 20));
+        }
+
+        #[test]
+        fn test_top_k_by() {
+            let words = ["pear", "fig", "banana", "kiwi", "apple"];
+
+            let longest = top_k_by(words, 2, |a, b| b.len().cmp(&a.len()));
+
+            assert_eq!(vec!["banana", "apple"], longest);
+        }
+    }
+}
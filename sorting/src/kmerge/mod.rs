@@ -0,0 +1,284 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::heap_sort::{BinaryHeap, Compare};
+
+// Merges any number of sorted sources into one sorted iterator.
+// https://en.wikipedia.org/wiki/K-way_merge_algorithm
+//
+// The head of every source is kept in a binary min-heap, so each item costs O(log k)
+// comparisons for k sources. The merge is stable: equal items come out in the order of their
+// sources, and items from the same source keep their order.
+pub fn kmerge<S, I>(sources: S) -> KMerge<I::IntoIter>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    kmerge_by(sources, I::Item::cmp)
+}
+
+// Same as kmerge, but orders items using the comparator function.
+pub fn kmerge_by<S, I, F>(sources: S, compare: F) -> KMergeBy<I::IntoIter, F>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    let mut sources: Vec<I::IntoIter> = sources.into_iter().map(|s| s.into_iter()).collect();
+    let heads = sources
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(source_idx, source)| source.next().map(|item| Head { item, source_idx }))
+        .collect();
+
+    KMergeBy {
+        sources,
+        heap: BinaryHeap::from_vec_by(heads, HeadOrder(compare)),
+    }
+}
+
+pub type KMerge<I> = KMergeBy<I, fn(&<I as Iterator>::Item, &<I as Iterator>::Item) -> Ordering>;
+
+struct Head<T> {
+    item: T,
+    source_idx: usize,
+}
+
+pub struct KMergeBy<I: Iterator, F> {
+    sources: Vec<I>,
+    // Min-heap of the next item of every source that is not exhausted.
+    heap: BinaryHeap<Head<I::Item>, HeadOrder<F>>,
+}
+
+// Orders heads by item, then by source so that the merge is stable.
+struct HeadOrder<F>(F);
+
+impl<T, F> Compare<Head<T>> for HeadOrder<F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn compare(&mut self, a: &Head<T>, b: &Head<T>) -> Ordering {
+        (self.0)(&a.item, &b.item).then(a.source_idx.cmp(&b.source_idx))
+    }
+}
+
+impl<I, F> Iterator for KMergeBy<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source_idx = self.heap.peek()?.source_idx;
+        let head = match self.sources[source_idx].next() {
+            Some(next) => self.heap.replace_top(Head {
+                item: next,
+                source_idx,
+            }),
+            None => self.heap.pop(),
+        };
+
+        head.map(|head| head.item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources.iter().map(|source| source.size_hint()).fold(
+            (self.heap.len(), Some(self.heap.len())),
+            |(lo, hi), (l, h)| {
+                (
+                    lo.saturating_add(l),
+                    hi.and_then(|hi| h.and_then(|h| hi.checked_add(h))),
+                )
+            },
+        )
+    }
+}
+
+impl<I, F> fmt::Debug for KMergeBy<I, F>
+where
+    I: Iterator + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KMergeBy")
+            .field("sources", &self.sources)
+            .finish()
+    }
+}
+
+// Merges the sorted slices in sources into out without allocating. out must be exactly as
+// long as all sources together. Each slice in sources is advanced past the items taken from it,
+// so they are all empty afterwards. Ties are taken from the earliest source, so the merge is
+// stable.
+//
+// The smallest head is found by scanning every source, which costs O(k) comparisons per item
+// for k sources. That is cheaper than a heap for a handful of sources; for many sources use
+// kmerge, which needs O(k) extra memory but only O(log k) comparisons per item.
+pub fn kmerge_into<T: Ord + Copy>(sources: &mut [&[T]], out: &mut [T]) {
+    kmerge_into_by(sources, out, T::cmp);
+}
+
+// Same as kmerge_into, but orders items using the comparator function.
+pub fn kmerge_into_by<T, F>(sources: &mut [&[T]], out: &mut [T], mut compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let total: usize = sources.iter().map(|source| source.len()).sum();
+    assert_eq!(
+        total,
+        out.len(),
+        "out must hold exactly as many items as all sources together"
+    );
+
+    for slot in out.iter_mut() {
+        let mut min: Option<(usize, &T)> = None;
+        for (source_idx, source) in sources.iter().enumerate() {
+            let Some(head) = source.first() else {
+                continue;
+            };
+            // Only a strictly smaller head replaces the current minimum.
+            match min {
+                Some((_, min_head)) if compare(head, min_head) != Ordering::Less => {}
+                _ => min = Some((source_idx, head)),
+            }
+        }
+
+        // out is exactly as long as the sources, so some source still has items.
+        let (min_idx, &head) = min.expect("a source with items left");
+        *slot = head;
+        sources[min_idx] = &sources[min_idx][1..];
+    }
+}
+
+#[cfg(test)]
+mod test_kmerge {
+    mod test_kmerge_iterator {
+        use super::super::{kmerge, kmerge_by};
+
+        #[test]
+        fn test_no_sources() {
+            let sources: Vec<Vec<i32>> = vec![];
+
+            assert_eq!(0, kmerge(sources).count());
+        }
+
+        #[test]
+        fn test_empty_sources() {
+            let sources: Vec<Vec<i32>> = vec![vec![], vec![1], vec![]];
+
+            assert_eq!(vec![1], kmerge(sources).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_slices() {
+            let a = [1, 4, 7, 10];
+            let b = [2, 5];
+            let c = [0, 3, 6, 8, 9, 11];
+
+            let merged: Vec<i32> = kmerge([&a[..], &b[..], &c[..]]).copied().collect();
+
+            assert_eq!((0..12).collect::<Vec<_>>(), merged);
+        }
+
+        #[test]
+        fn test_iterators() {
+            let sources = (1..=5u64).map(|step| (0..100).step_by(step as usize));
+            let mut expected: Vec<u64> = (1..=5u64)
+                .flat_map(|step| (0..100).step_by(step as usize))
+                .collect();
+            expected.sort();
+
+            assert_eq!(expected, kmerge(sources).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_many_sources() {
+            let sources: Vec<Vec<u32>> = (0..64)
+                .map(|s| (0..50).map(|i| (i * 64 + s) % 997).collect::<Vec<_>>())
+                .map(|mut v| {
+                    v.sort();
+                    v
+                })
+                .collect();
+            let mut expected: Vec<u32> = sources.concat();
+            expected.sort();
+
+            let merged = kmerge(sources);
+
+            assert_eq!((3200, Some(3200)), merged.size_hint());
+            assert_eq!(expected, merged.collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_stability() {
+            // The second field records the source an item came from.
+            let sources = vec![
+                vec![(1, 'a'), (2, 'a'), (2, 'a')],
+                vec![(1, 'b'), (2, 'b'), (3, 'b')],
+                vec![(0, 'c'), (2, 'c')],
+            ];
+
+            let merged: String = kmerge_by(sources, |a, b| a.0.cmp(&b.0))
+                .map(|p| p.1)
+                .collect();
+
+            assert_eq!("cabaabcb", merged);
+        }
+
+        #[test]
+        fn test_descending() {
+            let sources = vec![vec![9, 5, 1], vec![8, 5, 2], vec![7]];
+
+            let merged: Vec<i32> = kmerge_by(sources, |a, b| b.cmp(a)).collect();
+
+            assert_eq!(vec![9, 8, 7, 5, 5, 2, 1], merged);
+        }
+    }
+
+    mod test_kmerge_into {
+        use super::super::{kmerge_into, kmerge_into_by};
+
+        #[test]
+        fn test_no_sources() {
+            let mut out: [i32; 0] = [];
+
+            kmerge_into(&mut [], &mut out);
+        }
+
+        #[test]
+        fn test_slices() {
+            let a = [1, 4, 7, 10];
+            let b = [2, 5];
+            let c = [0, 3, 6, 8, 9, 11];
+            let mut sources = [&a[..], &[], &b[..], &c[..]];
+            let mut out = [0; 12];
+
+            kmerge_into(&mut sources, &mut out);
+
+            assert_eq!(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], &out);
+            assert!(sources.iter().all(|source| source.is_empty()));
+        }
+
+        #[test]
+        fn test_stability() {
+            let a = [(1, 'a'), (2, 'a'), (2, 'a')];
+            let b = [(1, 'b'), (2, 'b'), (3, 'b')];
+            let c = [(0, 'c'), (2, 'c')];
+            let mut out = [(0, ' '); 8];
+
+            kmerge_into_by(&mut [&a, &b, &c], &mut out, |x, y| x.0.cmp(&y.0));
+
+            assert_eq!("cabaabcb", out.iter().map(|p| p.1).collect::<String>());
+        }
+
+        #[test]
+        #[should_panic(expected = "out must hold exactly")]
+        fn test_output_length_mismatch() {
+            let mut out = [0; 2];
+
+            kmerge_into(&mut [&[1, 2], &[3]], &mut out);
+        }
+    }
+}
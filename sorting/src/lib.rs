@@ -1 +1,16 @@
-pub mod merge_sort;
\ No newline at end of file
+pub mod bucket_sort;
+pub mod counting_sort;
+pub mod external_sort;
+pub mod heap_sort;
+pub mod kmerge;
+pub mod loser_tree;
+pub mod merge_sort;
+pub mod quick_sort;
+pub mod radix_sort;
+pub mod sample_sort;
+pub mod sorter;
+pub mod sorting_network;
+pub mod tim_sort;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -0,0 +1,294 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// Tournament tree that keeps the loser of every match in the internal nodes.
+// https://en.wikipedia.org/wiki/K-way_merge_algorithm#Tournament_Tree
+//
+// Each of the k leaves holds the current head of one source. Replacing the overall winner only
+// replays the matches on the path from its leaf to the root, which takes ceil(log2 k)
+// comparisons (exactly log2 k when k is a power of two). A binary heap needs up to two
+// comparisons per level for the same update.
+//
+// Ties are won by the source with the lower index, so merges built on the tree are stable.
+// An exhausted source is represented by a None head, which loses every match without calling
+// the comparator.
+pub struct LoserTree<T, F> {
+    // tree[0] is the overall winner, tree[1..k] the loser of the match at each internal node.
+    // Leaf i is node k + i, and the parent of node n is n / 2.
+    tree: Vec<usize>,
+    heads: Vec<Option<T>>,
+    compare: F,
+}
+
+impl<T, F> LoserTree<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Builds the tree from the first item of every source, None for empty sources.
+    pub fn new(heads: Vec<Option<T>>, compare: F) -> Self {
+        let k = heads.len();
+        let mut tree = LoserTree {
+            tree: vec![0; k],
+            heads,
+            compare,
+        };
+        if k > 0 {
+            tree.tree[0] = tree.build(1);
+        }
+        tree
+    }
+
+    // Plays the matches of the subtree under node and returns its winner.
+    fn build(&mut self, node: usize) -> usize {
+        let k = self.heads.len();
+        if node >= k {
+            return node - k;
+        }
+
+        let left = self.build(2 * node);
+        let right = self.build(2 * node + 1);
+        if self.beats(left, right) {
+            self.tree[node] = right;
+            left
+        } else {
+            self.tree[node] = left;
+            right
+        }
+    }
+
+    // True if source a's head comes before source b's head.
+    fn beats(&mut self, a: usize, b: usize) -> bool {
+        match (&self.heads[a], &self.heads[b]) {
+            (Some(x), Some(y)) => match (self.compare)(x, y) {
+                Ordering::Equal => a < b,
+                ordering => ordering == Ordering::Less,
+            },
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => a < b,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heads.is_empty()
+    }
+
+    // Index of the source with the smallest head, or None once every source is exhausted.
+    pub fn winner(&self) -> Option<usize> {
+        let winner = *self.tree.first()?;
+        self.heads[winner].as_ref().map(|_| winner)
+    }
+
+    // Smallest head of all sources.
+    pub fn peek(&self) -> Option<&T> {
+        self.heads[self.winner()?].as_ref()
+    }
+
+    // Replaces the winner's head with next, the following item of its source or None if the
+    // source is exhausted, and returns the old head. Returns None without changing anything
+    // once every source is exhausted.
+    pub fn replace_winner(&mut self, next: Option<T>) -> Option<T> {
+        let mut winner = self.winner()?;
+        let head = std::mem::replace(&mut self.heads[winner], next);
+
+        let k = self.heads.len();
+        let mut node = (winner + k) / 2;
+        while node > 0 {
+            if self.beats(self.tree[node], winner) {
+                std::mem::swap(&mut self.tree[node], &mut winner);
+            }
+            node /= 2;
+        }
+        self.tree[0] = winner;
+
+        head
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for LoserTree<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoserTree")
+            .field("tree", &self.tree)
+            .field("heads", &self.heads)
+            .finish()
+    }
+}
+
+// Merges any number of sorted sources into one sorted iterator using a LoserTree.
+// Equivalent to kmerge, with fewer comparisons per item. On 2^18 i32s, where comparisons are
+// cheap, both take about the same time for 4 to 1024 sources; the tree pays off when the
+// comparator is expensive.
+pub fn loser_tree_merge<S, I>(sources: S) -> LoserTreeMerge<I::IntoIter>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    loser_tree_merge_by(sources, I::Item::cmp)
+}
+
+// Same as loser_tree_merge, but orders items using the comparator function.
+pub fn loser_tree_merge_by<S, I, F>(sources: S, compare: F) -> LoserTreeMergeBy<I::IntoIter, F>
+where
+    S: IntoIterator<Item = I>,
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    let mut sources: Vec<I::IntoIter> = sources.into_iter().map(|s| s.into_iter()).collect();
+    let heads = sources.iter_mut().map(|source| source.next()).collect();
+
+    LoserTreeMergeBy {
+        tree: LoserTree::new(heads, compare),
+        sources,
+    }
+}
+
+pub type LoserTreeMerge<I> =
+    LoserTreeMergeBy<I, fn(&<I as Iterator>::Item, &<I as Iterator>::Item) -> Ordering>;
+
+pub struct LoserTreeMergeBy<I: Iterator, F> {
+    tree: LoserTree<I::Item, F>,
+    sources: Vec<I>,
+}
+
+impl<I, F> Iterator for LoserTreeMergeBy<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let winner = self.tree.winner()?;
+        let next = self.sources[winner].next();
+        self.tree.replace_winner(next)
+    }
+}
+
+impl<I, F> fmt::Debug for LoserTreeMergeBy<I, F>
+where
+    I: Iterator + fmt::Debug,
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoserTreeMergeBy")
+            .field("tree", &self.tree)
+            .field("sources", &self.sources)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_loser_tree {
+    mod test_tree {
+        use super::super::LoserTree;
+
+        #[test]
+        fn test_no_sources() {
+            let mut tree = LoserTree::new(Vec::<Option<i32>>::new(), i32::cmp);
+
+            assert!(tree.is_empty());
+            assert_eq!(None, tree.winner());
+            assert_eq!(None, tree.replace_winner(Some(1)));
+        }
+
+        #[test]
+        fn test_single_source() {
+            let mut tree = LoserTree::new(vec![Some(4)], i32::cmp);
+
+            assert_eq!(Some(0), tree.winner());
+            assert_eq!(Some(4), tree.replace_winner(Some(5)));
+            assert_eq!(Some(&5), tree.peek());
+            assert_eq!(Some(5), tree.replace_winner(None));
+            assert_eq!(None, tree.winner());
+        }
+
+        #[test]
+        fn test_winner() {
+            let tree = LoserTree::new(vec![Some(3), None, Some(1), Some(2), Some(1)], i32::cmp);
+
+            assert_eq!(5, tree.len());
+            assert_eq!(Some(2), tree.winner());
+            assert_eq!(Some(&1), tree.peek());
+        }
+
+        #[test]
+        fn test_exact_comparisons() {
+            // With 8 sources every replacement replays exactly log2(8) = 3 matches.
+            let count = std::cell::Cell::new(0);
+            let compare = |a: &u32, b: &u32| {
+                count.set(count.get() + 1);
+                a.cmp(b)
+            };
+            let heads = (0..8u32).map(|i| Some(i * 3 % 8)).collect();
+            let mut tree = LoserTree::new(heads, compare);
+            assert_eq!(7, count.get());
+
+            for round in 1..=100 {
+                let head = *tree.peek().unwrap();
+                tree.replace_winner(Some(head + 8));
+                assert_eq!(7 + 3 * round, count.get());
+            }
+        }
+    }
+
+    mod test_loser_tree_merge {
+        use super::super::{loser_tree_merge, loser_tree_merge_by};
+
+        #[test]
+        fn test_no_sources() {
+            let sources: Vec<Vec<i32>> = vec![];
+
+            assert_eq!(0, loser_tree_merge(sources).count());
+        }
+
+        #[test]
+        fn test_slices() {
+            let a = [1, 4, 7, 10];
+            let b: [i32; 0] = [];
+            let c = [2, 5];
+            let d = [0, 3, 6, 8, 9, 11];
+
+            let merged: Vec<i32> = loser_tree_merge([&a[..], &b[..], &c[..], &d[..]])
+                .copied()
+                .collect();
+
+            assert_eq!((0..12).collect::<Vec<_>>(), merged);
+        }
+
+        #[test]
+        fn test_source_counts() {
+            for k in 1..=33u32 {
+                let sources: Vec<Vec<u32>> = (0..k)
+                    .map(|s| (0..s % 7 + 3).map(|i| i * k + s % 5).collect())
+                    .collect();
+                let mut expected = sources.concat();
+                expected.sort();
+
+                let merged: Vec<u32> = loser_tree_merge(sources).collect();
+
+                assert_eq!(expected, merged, "k = {}", k);
+            }
+        }
+
+        #[test]
+        fn test_stability() {
+            // The second field records the source an item came from.
+            let sources = vec![
+                vec![(1, 'a'), (2, 'a'), (2, 'a')],
+                vec![(1, 'b'), (2, 'b'), (3, 'b')],
+                vec![(0, 'c'), (2, 'c')],
+            ];
+
+            let merged: String = loser_tree_merge_by(sources, |a, b| a.0.cmp(&b.0))
+                .map(|p| p.1)
+                .collect();
+
+            assert_eq!("cabaabcb", merged);
+        }
+    }
+}
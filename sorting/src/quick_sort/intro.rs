@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+use super::{hoare_partition, insertion_sort, recurse_smaller, Pivot, PivotChooser, DEFAULT_SEED};
+use crate::heap_sort::heap_sort_by;
+
+// Slices this short are finished with insertion sort.
+const INSERTION_SORT_CUTOFF: usize = 16;
+
+// Introsort: quicksort with Hoare partitioning around the median of three, which switches to
+// heapsort for a slice once the recursion gets deeper than 2 log2 n. That bounds the worst case
+// at O(n log n) comparisons while keeping quicksort's speed on typical input. Not stable.
+// https://en.wikipedia.org/wiki/Introsort
+pub fn intro_sort<T: Ord>(input: &mut [T]) {
+    intro_sort_by(input, T::cmp);
+}
+
+// Same as intro_sort, but orders elements using the comparator function.
+pub fn intro_sort_by<T, F>(input: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let depth_limit = 2 * (usize::BITS - input.len().leading_zeros());
+    let mut chooser = PivotChooser {
+        pivot: Pivot::MedianOfThree,
+        state: DEFAULT_SEED,
+    };
+    intro_split(input, depth_limit, &mut chooser, &mut compare);
+}
+
+// Same as intro_sort, but orders elements by the key extracted with f.
+pub fn intro_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    intro_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+fn intro_split<T, F>(
+    mut input: &mut [T],
+    mut depth_limit: u32,
+    chooser: &mut PivotChooser,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while input.len() > INSERTION_SORT_CUTOFF {
+        if depth_limit == 0 {
+            heap_sort_by(input, compare);
+            return;
+        }
+        depth_limit -= 1;
+
+        let pivot_idx = hoare_partition(input, chooser, compare);
+        input = recurse_smaller(input, pivot_idx, pivot_idx + 1, |part| {
+            intro_split(part, depth_limit, chooser, compare)
+        });
+    }
+    insertion_sort(input, compare);
+}
+
+#[cfg(test)]
+mod test_intro {
+    mod test_intro_sort {
+        use super::super::intro_sort;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            intro_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            intro_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            intro_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+
+            intro_sort(&mut input);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_every_length() {
+            for length in 0..300u64 {
+                let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 37).collect();
+                let mut expected = input.clone();
+                expected.sort();
+
+                intro_sort(&mut input);
+
+                assert_eq!(expected, input, "length {}", length);
+            }
+        }
+    }
+
+    mod test_intro_sort_by {
+        use super::super::{intro_sort_by, intro_sort_by_key};
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            intro_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_by_key() {
+            let mut input: Vec<(u64, u64)> = (0..500).map(|i| ((i * 7919) % 101, i)).collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| std::cmp::Reverse(p.0));
+
+            intro_sort_by_key(&mut input, |p| std::cmp::Reverse(p.0));
+
+            let keys = |v: &[(u64, u64)]| v.iter().map(|p| p.0).collect::<Vec<_>>();
+            assert_eq!(keys(&expected), keys(&input));
+        }
+    }
+}
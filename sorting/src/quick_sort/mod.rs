@@ -0,0 +1,834 @@
+use std::cmp::Ordering;
+
+mod intro;
+mod pdq;
+pub use intro::{intro_sort, intro_sort_by, intro_sort_by_key};
+pub use pdq::{pdq_sort, pdq_sort_by, pdq_sort_by_key};
+
+// Quicksort: partition the input around a pivot, then sort both sides.
+// https://en.wikipedia.org/wiki/Quicksort
+//
+// Quicksort is in place and not stable. Every partition scheme recurses into the smaller parts
+// and loops on the largest one, so the stack stays O(log n) deep even when a bad pivot makes
+// the running time O(n^2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Partition {
+    // Single scan that moves smaller elements to the front. Simple, but O(n^2) on inputs with
+    // many equal elements, because equal elements all end up on one side.
+    Lomuto,
+    // Two scans towards each other that stop on equal elements, so duplicates are split evenly.
+    Hoare,
+    // Dijkstra's Dutch national flag partition into <, == and > the pivot. Elements equal to the
+    // pivot are never looked at again, which makes duplicate heavy inputs fast.
+    ThreeWay,
+    // Yaroslavskiy's dual-pivot partition into < p, p..=q and > q, as used by Java's
+    // Arrays.sort. The pivots are chosen from the first and the second half of the input.
+    DualPivot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pivot {
+    // The first element. O(n^2) on sorted and reversed input.
+    First,
+    // An element picked with a pseudo-random generator seeded by QuickSorter::seed.
+    Random,
+    // The median of the elements at a quarter, half and three quarters of the input. Sampling
+    // the first and last element instead picks the second largest element at every level of a
+    // reversed input.
+    MedianOfThree,
+    // Tukey's ninther: the median of the medians of three evenly spaced groups of three.
+    Ninther,
+}
+
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Sorts input with Hoare partitioning around the median of three elements.
+pub fn quick_sort<T: Ord>(input: &mut [T]) {
+    quick_sort_by(input, T::cmp);
+}
+
+// Same as quick_sort, but orders elements using the comparator function.
+pub fn quick_sort_by<T, F>(input: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    QuickSorter::new(Partition::Hoare).sort_by(input, compare);
+}
+
+// Same as quick_sort, but orders elements by the key extracted with f.
+pub fn quick_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    quick_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Quicksort with a choice of partition scheme and pivot strategy. The pivot defaults to the
+// median of three. Random pivots are reproducible: every sort starts from the same seed.
+#[derive(Debug, Clone)]
+pub struct QuickSorter {
+    partition: Partition,
+    pivot: Pivot,
+    seed: u64,
+}
+
+impl QuickSorter {
+    pub fn new(partition: Partition) -> Self {
+        QuickSorter {
+            partition,
+            pivot: Pivot::MedianOfThree,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    pub fn pivot(mut self, pivot: Pivot) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    // Seed of the generator used by Pivot::Random.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn sort<T: Ord>(&self, input: &mut [T]) {
+        self.sort_by(input, T::cmp);
+    }
+
+    pub fn sort_by<T, F>(&self, input: &mut [T], mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut chooser = PivotChooser {
+            pivot: self.pivot,
+            state: self.seed,
+        };
+        match self.partition {
+            Partition::Lomuto => lomuto_quick_sort(input, &mut chooser, &mut compare),
+            Partition::Hoare => hoare_quick_sort(input, &mut chooser, &mut compare),
+            Partition::ThreeWay => three_way_quick_sort(input, &mut chooser, &mut compare),
+            Partition::DualPivot => dual_pivot_quick_sort(input, &mut chooser, &mut compare),
+        }
+    }
+
+    pub fn sort_by_key<T, K, F>(&self, input: &mut [T], mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(input, |a, b| f(a).cmp(&f(b)));
+    }
+}
+
+struct PivotChooser {
+    pivot: Pivot,
+    // Linear congruential generator state for Pivot::Random.
+    state: u64,
+}
+
+impl PivotChooser {
+    // Index of the pivot in input, which must not be empty.
+    fn choose<T, F>(&mut self, input: &[T], compare: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let last = input.len() - 1;
+        let middle = input.len() / 2;
+        match self.pivot {
+            Pivot::First => 0,
+            Pivot::Random => {
+                self.state = self
+                    .state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ((self.state >> 33) % input.len() as u64) as usize
+            }
+            Pivot::MedianOfThree => quartile_median(input, compare),
+            Pivot::Ninther if input.len() < 9 => quartile_median(input, compare),
+            Pivot::Ninther => {
+                let step = input.len() / 8;
+                let a = median_of_three(input, 0, step, 2 * step, compare);
+                let b = median_of_three(input, middle - step, middle, middle + step, compare);
+                let c = median_of_three(input, last - 2 * step, last - step, last, compare);
+                median_of_three(input, a, b, c, compare)
+            }
+        }
+    }
+}
+
+// Index of the median of the elements at a quarter, half and three quarters of input.
+fn quartile_median<T, F>(input: &[T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let quarter = input.len() / 4;
+    median_of_three(
+        input,
+        quarter,
+        input.len() / 2,
+        input.len() - 1 - quarter,
+        compare,
+    )
+}
+
+// Index of the median of input[a], input[b] and input[c].
+fn median_of_three<T, F>(input: &[T], a: usize, b: usize, c: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let less =
+        |compare: &mut F, x: usize, y: usize| compare(&input[x], &input[y]) == Ordering::Less;
+    if less(compare, a, b) {
+        if less(compare, b, c) {
+            b
+        } else if less(compare, a, c) {
+            c
+        } else {
+            a
+        }
+    } else if less(compare, a, c) {
+        a
+    } else if less(compare, b, c) {
+        c
+    } else {
+        b
+    }
+}
+
+// Sorts the smaller of input[..left_end] and input[right_start..] with sort and returns the
+// larger one, for the caller to continue with.
+fn recurse_smaller<T, S>(
+    input: &mut [T],
+    left_end: usize,
+    right_start: usize,
+    mut sort: S,
+) -> &mut [T]
+where
+    S: FnMut(&mut [T]),
+{
+    let (left, rest) = input.split_at_mut(left_end);
+    let right = &mut rest[right_start - left_end..];
+    if left.len() < right.len() {
+        sort(left);
+        right
+    } else {
+        sort(right);
+        left
+    }
+}
+
+// Sorts input by inserting every element into the sorted prefix before it. O(n^2), but the
+// fastest choice for the short slices the quicksorts bottom out in.
+fn insertion_sort<T, F>(input: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 1..input.len() {
+        let mut j = i;
+        while j > 0 && compare(&input[i], &input[j - 1]) == Ordering::Less {
+            j -= 1;
+        }
+        input[j..=i].rotate_right(1);
+    }
+}
+
+fn lomuto_quick_sort<T, F>(mut input: &mut [T], chooser: &mut PivotChooser, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while input.len() > 1 {
+        let pivot_idx = lomuto_partition(input, chooser, compare);
+        input = recurse_smaller(input, pivot_idx, pivot_idx + 1, |part| {
+            lomuto_quick_sort(part, chooser, compare)
+        });
+    }
+}
+
+// Moves the elements less than the pivot before it and returns the pivot's final index.
+fn lomuto_partition<T, F>(input: &mut [T], chooser: &mut PivotChooser, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let last = input.len() - 1;
+    let pivot_idx = chooser.choose(input, compare);
+    input.swap(pivot_idx, last);
+
+    let mut store_idx = 0;
+    for i in 0..last {
+        if compare(&input[i], &input[last]) == Ordering::Less {
+            input.swap(i, store_idx);
+            store_idx += 1;
+        }
+    }
+    input.swap(store_idx, last);
+
+    store_idx
+}
+
+fn hoare_quick_sort<T, F>(mut input: &mut [T], chooser: &mut PivotChooser, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while input.len() > 1 {
+        let pivot_idx = hoare_partition(input, chooser, compare);
+        input = recurse_smaller(input, pivot_idx, pivot_idx + 1, |part| {
+            hoare_quick_sort(part, chooser, compare)
+        });
+    }
+}
+
+// Hoare's scheme with the pivot kept at the front during the scans (Sedgewick's variant), so
+// it can be swapped into its final place afterwards. Returns the pivot's final index.
+fn hoare_partition<T, F>(input: &mut [T], chooser: &mut PivotChooser, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let last = input.len() - 1;
+    let pivot_idx = chooser.choose(input, compare);
+    input.swap(0, pivot_idx);
+
+    let (mut i, mut j) = (0, input.len());
+    loop {
+        // Both scans stop on elements equal to the pivot.
+        i += 1;
+        while i < last && compare(&input[i], &input[0]) == Ordering::Less {
+            i += 1;
+        }
+        // input[0] itself stops the scan.
+        j -= 1;
+        while compare(&input[0], &input[j]) == Ordering::Less {
+            j -= 1;
+        }
+        if i >= j {
+            break;
+        }
+        input.swap(i, j);
+    }
+    input.swap(0, j);
+
+    j
+}
+
+fn three_way_quick_sort<T, F>(mut input: &mut [T], chooser: &mut PivotChooser, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while input.len() > 1 {
+        let (lt, gt) = three_way_partition(input, chooser, compare);
+        input = recurse_smaller(input, lt, gt, |part| {
+            three_way_quick_sort(part, chooser, compare)
+        });
+    }
+}
+
+// Rearranges input into [< pivot | == pivot | > pivot] and returns the bounds of the middle.
+fn three_way_partition<T, F>(
+    input: &mut [T],
+    chooser: &mut PivotChooser,
+    compare: &mut F,
+) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let pivot_idx = chooser.choose(input, compare);
+    input.swap(0, pivot_idx);
+
+    // input[..lt] < pivot, input[lt..i] == pivot, input[gt..] > pivot. input[lt..i] always
+    // holds at least one element, so input[lt] can stand in for the pivot.
+    let (mut lt, mut i, mut gt) = (0, 1, input.len());
+    while i < gt {
+        match compare(&input[i], &input[lt]) {
+            Ordering::Less => {
+                input.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                input.swap(i, gt);
+            }
+            Ordering::Equal => i += 1,
+        }
+    }
+
+    (lt, gt)
+}
+
+fn dual_pivot_quick_sort<T, F>(mut input: &mut [T], chooser: &mut PivotChooser, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while input.len() > 1 {
+        let (lt, middle_start, middle_end, gt) = dual_pivot_partition(input, chooser, compare);
+
+        // Sort the smallest two of the three parts recursively and continue with the largest.
+        let (left, rest) = input.split_at_mut(lt);
+        let (middle, right) = rest.split_at_mut(gt - lt);
+        let middle = &mut middle[middle_start - lt..middle_end - lt];
+        let right = &mut right[1..];
+        let mut parts = [left, middle, right];
+        parts.sort_by_key(|part| part.len());
+        let [smallest, smaller, largest] = parts;
+        dual_pivot_quick_sort(smallest, chooser, compare);
+        dual_pivot_quick_sort(smaller, chooser, compare);
+        input = largest;
+    }
+}
+
+// Rearranges input into [< p | p | p..=q | q | > q] for two pivots p <= q. Returns the final
+// index of p, the bounds of the part of the middle that still needs sorting, and the final
+// index of q.
+//
+// When the middle is large, copies of p and q are moved out of it first, so inputs with few
+// distinct values don't recurse on the same values over and over. If p == q the middle holds
+// only copies of the pivot and needs no sorting at all.
+fn dual_pivot_partition<T, F>(
+    input: &mut [T],
+    chooser: &mut PivotChooser,
+    compare: &mut F,
+) -> (usize, usize, usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let length = input.len();
+    let last = length - 1;
+    let half = length / 2;
+    let p_idx = chooser.choose(&input[..half], compare);
+    let q_idx = half + chooser.choose(&input[half..], compare);
+    input.swap(0, p_idx);
+    input.swap(last, q_idx);
+    if compare(&input[0], &input[last]) == Ordering::Greater {
+        input.swap(0, last);
+    }
+
+    // input[1..lt] < p, input[lt..k] in p..=q, input[gt + 1..last] > q.
+    let (mut lt, mut k, mut gt) = (1, 1, last - 1);
+    while k <= gt {
+        if compare(&input[k], &input[0]) == Ordering::Less {
+            input.swap(k, lt);
+            lt += 1;
+        } else if compare(&input[k], &input[last]) == Ordering::Greater {
+            while k < gt && compare(&input[gt], &input[last]) == Ordering::Greater {
+                gt -= 1;
+            }
+            input.swap(k, gt);
+            gt -= 1;
+            if compare(&input[k], &input[0]) == Ordering::Less {
+                input.swap(k, lt);
+                lt += 1;
+            }
+        }
+        k += 1;
+    }
+    lt -= 1;
+    gt += 1;
+    input.swap(0, lt);
+    input.swap(last, gt);
+
+    if compare(&input[lt], &input[gt]) == Ordering::Equal {
+        return (lt, lt + 1, lt + 1, gt);
+    }
+
+    let (mut middle_start, mut middle_end) = (lt + 1, gt);
+    // Like Java's DualPivotQuicksort, only bother when the middle is over 4/7 of the input.
+    if middle_end - middle_start > length / 7 * 4 {
+        let mut k = middle_start;
+        while k < middle_end {
+            if compare(&input[k], &input[lt]) == Ordering::Equal {
+                input.swap(k, middle_start);
+                middle_start += 1;
+                k += 1;
+            } else if compare(&input[k], &input[gt]) == Ordering::Equal {
+                middle_end -= 1;
+                input.swap(k, middle_end);
+            } else {
+                k += 1;
+            }
+        }
+    }
+
+    (lt, middle_start, middle_end, gt)
+}
+
+#[cfg(test)]
+mod test_quick_sort {
+    // Sorts every input from the merge sort test cases with every partition scheme and pivot
+    // strategy.
+    fn for_every_sorter(mut test: impl FnMut(super::QuickSorter)) {
+        use super::{Partition, Pivot, QuickSorter};
+
+        let partitions = [
+            Partition::Lomuto,
+            Partition::Hoare,
+            Partition::ThreeWay,
+            Partition::DualPivot,
+        ];
+        let pivots = [
+            Pivot::First,
+            Pivot::Random,
+            Pivot::MedianOfThree,
+            Pivot::Ninther,
+        ];
+        for partition in partitions {
+            for pivot in pivots {
+                test(QuickSorter::new(partition).pivot(pivot));
+            }
+        }
+    }
+
+    mod test_sort {
+        use super::super::quick_sort;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            quick_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            quick_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_small_arrays() {
+            let mut input = [2, 1];
+
+            quick_sort(&mut input);
+
+            assert_eq!(&[1, 2], &input);
+
+            let mut input = [4, 1, 2];
+
+            quick_sort(&mut input);
+
+            assert_eq!(&[1, 2, 4], &input);
+
+            let mut input = [4, 1, 2, 5];
+
+            quick_sort(&mut input);
+
+            assert_eq!(&[1, 2, 4, 5], &input);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            quick_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+
+            quick_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+        }
+
+        #[test]
+        fn test_non_copy_elements() {
+            let mut input: Vec<String> = ["pear", "apple", "fig", "banana", "apple"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+
+            quick_sort(&mut input);
+
+            assert_eq!(vec!["apple", "apple", "banana", "fig", "pear"], input);
+        }
+    }
+
+    mod test_sort_by {
+        use super::super::{quick_sort_by, quick_sort_by_key};
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            quick_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_by_key() {
+            let mut input = [(2u32, 'b'), (1, 'a'), (3, 'c'), (0, 'd')];
+
+            quick_sort_by_key(&mut input, |p| p.0);
+
+            assert_eq!(&[(0, 'd'), (1, 'a'), (2, 'b'), (3, 'c')], &input);
+        }
+    }
+
+    mod test_quick_sorter {
+        use super::for_every_sorter;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            for_every_sorter(|sorter| {
+                let mut src: [i32; 0] = [];
+
+                sorter.sort(&mut src);
+
+                assert!(src.is_empty())
+            });
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            for_every_sorter(|sorter| {
+                let mut src = [1];
+
+                sorter.sort(&mut src);
+
+                assert_eq!(1, src[0]);
+            });
+        }
+
+        #[test]
+        fn test_small_arrays() {
+            for_every_sorter(|sorter| {
+                let mut input = [2, 1];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[1, 2], &input, "{:?}", sorter);
+
+                let mut input = [4, 1, 2];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[1, 2, 4], &input, "{:?}", sorter);
+
+                let mut input = [4, 1, 2, 5];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[1, 2, 4, 5], &input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            for_every_sorter(|sorter| {
+                let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            for_every_sorter(|sorter| {
+                let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_u64_array() {
+            for_every_sorter(|sorter| {
+                let mut input = [u64::MAX, 7, 0, 42, 7, 1 << 40];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(&[0, 7, 7, 42, 1 << 40, u64::MAX], &input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_tuple_array() {
+            for_every_sorter(|sorter| {
+                let mut input = [(2u32, 1u32), (1, 9), (2, 0), (1, 3), (0, 5)];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(
+                    &[(0, 5), (1, 3), (1, 9), (2, 0), (2, 1)],
+                    &input,
+                    "{:?}",
+                    sorter
+                );
+            });
+        }
+
+        #[test]
+        fn test_str_array() {
+            for_every_sorter(|sorter| {
+                let mut input = ["pear", "apple", "fig", "banana", "apple"];
+
+                sorter.sort(&mut input);
+
+                assert_eq!(
+                    &["apple", "apple", "banana", "fig", "pear"],
+                    &input,
+                    "{:?}",
+                    sorter
+                );
+            });
+        }
+
+        #[test]
+        fn test_descending() {
+            for_every_sorter(|sorter| {
+                let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+                sorter.sort_by(&mut input, |a, b| b.cmp(a));
+
+                assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_every_length() {
+            for_every_sorter(|sorter| {
+                for length in 0..200u64 {
+                    let mut input: Vec<u64> = (0..length).map(|i| (i * 7919) % 37).collect();
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    sorter.sort(&mut input);
+
+                    assert_eq!(expected, input, "{:?}, length {}", sorter, length);
+                }
+            });
+        }
+
+        #[test]
+        fn test_random_array() {
+            for_every_sorter(|sorter| {
+                let mut input: Vec<u32> = random(2_000, 42);
+                let mut expected = input.clone();
+                expected.sort();
+
+                sorter.sort(&mut input);
+
+                assert_eq!(expected, input, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_sorted_and_duplicate_heavy_arrays() {
+            // Worst cases for some of the combinations, large enough that unbounded recursion
+            // would overflow the stack in a debug build.
+            for_every_sorter(|sorter| {
+                let mut sorted: Vec<u32> = (0..3_000).collect();
+                let expected = sorted.clone();
+
+                sorter.sort(&mut sorted);
+
+                assert_eq!(expected, sorted, "{:?}", sorter);
+
+                let mut two_values: Vec<u32> = (0..3_000).map(|i| i % 2).collect();
+                let mut expected = two_values.clone();
+                expected.sort();
+
+                sorter.sort(&mut two_values);
+
+                assert_eq!(expected, two_values, "{:?}", sorter);
+            });
+        }
+
+        #[test]
+        fn test_seed() {
+            use super::super::{Partition, Pivot, QuickSorter};
+
+            let mut input: Vec<u32> = (0..500).map(|i| (i * 7919) % 101).collect();
+            let mut expected = input.clone();
+            expected.sort();
+
+            QuickSorter::new(Partition::ThreeWay)
+                .pivot(Pivot::Random)
+                .seed(7)
+                .sort(&mut input);
+
+            assert_eq!(expected, input);
+        }
+    }
+
+    mod test_worst_case {
+        use super::super::{intro_sort_by, pdq_sort_by, Partition, QuickSorter};
+        use std::cmp::Ordering;
+
+        // McIlroy's adversary from "A Killer Adversary for Quicksort": element values are only
+        // fixed when a comparison forces it, and always so that the pivot candidate ends up
+        // among the smallest elements. Returns the number of comparisons sort made.
+        fn adversary_comparisons<S>(length: usize, sort: S) -> usize
+        where
+            S: FnOnce(&mut [usize], &mut dyn FnMut(&usize, &usize) -> Ordering),
+        {
+            let gas = length;
+            let mut values = vec![gas; length];
+            let mut solid = 0;
+            let mut candidate = 0;
+            let mut comparisons = 0;
+            let mut items: Vec<usize> = (0..length).collect();
+
+            sort(&mut items, &mut |&x, &y| {
+                comparisons += 1;
+                if values[x] == gas && values[y] == gas {
+                    let frozen = if x == candidate { x } else { y };
+                    values[frozen] = solid;
+                    solid += 1;
+                }
+                if values[x] == gas {
+                    candidate = x;
+                } else if values[y] == gas {
+                    candidate = y;
+                }
+                values[x].cmp(&values[y])
+            });
+
+            comparisons
+        }
+
+        #[test]
+        fn test_adversary() {
+            let length: usize = 2_000;
+            let bound = 4 * length * (usize::BITS - length.leading_zeros()) as usize;
+
+            let quick = adversary_comparisons(length, |items, compare| {
+                QuickSorter::new(Partition::Hoare).sort_by(items, compare)
+            });
+            let intro =
+                adversary_comparisons(length, |items, compare| intro_sort_by(items, compare));
+            let pdq = adversary_comparisons(length, |items, compare| pdq_sort_by(items, compare));
+
+            assert!(quick > bound, "quick sort: {} comparisons", quick);
+            assert!(intro <= bound, "intro sort: {} comparisons", intro);
+            assert!(pdq <= bound, "pdq sort: {} comparisons", pdq);
+        }
+    }
+
+    mod test_helpers {
+        use super::super::insertion_sort;
+
+        #[test]
+        fn test_insertion_sort() {
+            let mut input = [5, 2, 9, 2, 0, 7, 1];
+
+            insertion_sort(&mut input, &mut i32::cmp);
+
+            assert_eq!(&[0, 1, 2, 2, 5, 7, 9], &input);
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use std::cmp::Ordering;
+
+use rayon::prelude::*;
+
+use crate::quick_sort::pdq_sort_by;
+
+// Slices this short are sorted sequentially by sample_sort_par.
+const SEQUENTIAL_CUTOFF: usize = 1 << 14;
+
+// sample_sort_par splits the input into this many buckets per thread, so a thread that gets
+// a few large buckets doesn't hold up the others for long.
+const BUCKETS_PER_THREAD: usize = 8;
+
+// Splitters are picked from this many samples per bucket. More samples even out the bucket
+// sizes at the cost of sorting the sample.
+const OVERSAMPLING: usize = 16;
+
+// par_distribute hands out this many chunks per thread, and no chunk shorter than
+// MIN_CHUNK_LEN.
+const CHUNKS_PER_THREAD: usize = 4;
+const MIN_CHUNK_LEN: usize = 1 << 12;
+
+// https://en.wikipedia.org/wiki/Samplesort
+//
+// Picks up to buckets - 1 splitters from a sorted random sample of the input, moves every
+// element to the bucket between its splitters with par_distribute, then sorts the buckets in
+// parallel with pdq_sort. Elements equal to a splitter get a bucket of their own, which needs
+// no sorting, so a value that fills much of the input isn't left to a single thread. Unlike
+// top_down_merge_sort_par there is no final merge, so every step runs on all threads. Uses n
+// elements of extra memory. Not stable. On 40M random i32s and a single thread it takes about
+// 60% of the time of top_down_merge_sort_par and 15% more than pdq_sort.
+//
+// Sorts on rayon's global thread pool, or on the pool of the caller's ThreadPool::install.
+pub fn sample_sort_par<T: Ord + Copy + Send + Sync>(input: &mut [T]) {
+    sample_sort_par_by(input, T::cmp);
+}
+
+// Same as sample_sort_par, but orders elements using the comparator function.
+// The comparator is shared between worker threads, so it has to be Fn + Sync.
+pub fn sample_sort_par_by<T, F>(input: &mut [T], compare: F)
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    if input.len() <= SEQUENTIAL_CUTOFF {
+        pdq_sort_by(input, compare);
+        return;
+    }
+
+    let buckets = (rayon::current_num_threads() * BUCKETS_PER_THREAD)
+        .min(input.len() / SEQUENTIAL_CUTOFF)
+        .max(2);
+    let splitters = pick_splitters(input, buckets, &compare);
+    let bounds = distribute(input, &splitters, &compare);
+
+    // The odd buckets hold elements equal to a splitter.
+    split_buckets(input, &bounds)
+        .into_par_iter()
+        .step_by(2)
+        .for_each(|bucket| pdq_sort_by(bucket, &compare));
+}
+
+// Same as sample_sort_par, but orders elements by the key extracted with f.
+pub fn sample_sort_par_by_key<T, K, F>(input: &mut [T], f: F)
+where
+    T: Copy + Send + Sync,
+    K: Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    sample_sort_par_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Picks buckets - 1 splitters from a sorted random sample of input, in order and without
+// repeats: a value that fills more than a bucket's worth of the sample is picked once.
+fn pick_splitters<T, F>(input: &[T], buckets: usize, compare: &F) -> Vec<T>
+where
+    T: Copy,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut samples: Vec<T> = (0..buckets * OVERSAMPLING)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            input[(state >> 33) as usize % input.len()]
+        })
+        .collect();
+    pdq_sort_by(&mut samples, compare);
+    let mut splitters: Vec<T> = samples
+        .into_iter()
+        .skip(OVERSAMPLING)
+        .step_by(OVERSAMPLING)
+        .collect();
+    splitters.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+    splitters
+}
+
+// Moves every element of input into its bucket and returns the bounds of the buckets. Bucket
+// 2i holds the elements between splitters i - 1 and i, and bucket 2i + 1 the elements equal
+// to splitter i.
+fn distribute<T, F>(input: &mut [T], splitters: &[T], compare: &F) -> Vec<usize>
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let mut work = input.to_vec();
+    par_distribute(input, &mut work, 2 * splitters.len() + 1, |x| {
+        let i = splitters.partition_point(|s| compare(s, x) == Ordering::Less);
+        match splitters.get(i) {
+            Some(s) if compare(s, x) == Ordering::Equal => 2 * i + 1,
+            _ => 2 * i,
+        }
+    })
+}
+
+// Groups input by the bucket that bucket_of returns for every element, which must be less
+// than buckets, and returns where the buckets start plus the end of the last one. Elements of
+// the same bucket keep their relative order.
+//
+// Every chunk of input is grouped into the same chunk of work in parallel, then every bucket
+// is copied together from the chunks into its place in input in parallel. bucket_of is called
+// twice per element, which is cheaper than storing the buckets. work must be at least as long
+// as input.
+pub fn par_distribute<T, F>(
+    input: &mut [T],
+    work: &mut [T],
+    buckets: usize,
+    bucket_of: F,
+) -> Vec<usize>
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T) -> usize + Sync,
+{
+    let work = &mut work[..input.len()];
+    let chunk_len = input
+        .len()
+        .div_ceil(rayon::current_num_threads() * CHUNKS_PER_THREAD)
+        .max(MIN_CHUNK_LEN);
+
+    // Where every bucket starts in every chunk of work, and how long it is there.
+    let chunk_buckets: Vec<(Vec<usize>, Vec<usize>)> = input
+        .par_chunks(chunk_len)
+        .zip(work.par_chunks_mut(chunk_len))
+        .map(|(src, dest)| {
+            let mut counts = vec![0; buckets];
+            for x in src.iter() {
+                counts[bucket_of(x)] += 1;
+            }
+            let mut starts = Vec::with_capacity(buckets);
+            let mut sum = 0;
+            for &count in counts.iter() {
+                starts.push(sum);
+                sum += count;
+            }
+
+            let mut next = starts.clone();
+            for x in src.iter() {
+                let b = bucket_of(x);
+                dest[next[b]] = *x;
+                next[b] += 1;
+            }
+            (starts, counts)
+        })
+        .collect();
+
+    let mut bounds = vec![0; buckets + 1];
+    for b in 0..buckets {
+        let count: usize = chunk_buckets.iter().map(|(_, counts)| counts[b]).sum();
+        bounds[b + 1] = bounds[b] + count;
+    }
+
+    let work = &*work;
+    split_buckets(input, &bounds)
+        .into_par_iter()
+        .enumerate()
+        .for_each(|(b, bucket)| {
+            let mut filled = 0;
+            for (c, (starts, counts)) in chunk_buckets.iter().enumerate() {
+                let start = c * chunk_len + starts[b];
+                let count = counts[b];
+                bucket[filled..filled + count].copy_from_slice(&work[start..start + count]);
+                filled += count;
+            }
+        });
+
+    bounds
+}
+
+// Splits input into the buckets between consecutive bounds.
+fn split_buckets<'a, T>(mut input: &'a mut [T], bounds: &[usize]) -> Vec<&'a mut [T]> {
+    let mut buckets = Vec::with_capacity(bounds.len() - 1);
+    for window in bounds.windows(2) {
+        let (bucket, rest) = input.split_at_mut(window[1] - window[0]);
+        buckets.push(bucket);
+        input = rest;
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod test_sample {
+    fn with_threads<R: Send>(num_threads: usize, f: impl FnOnce() -> R + Send) -> R {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap()
+            .install(f)
+    }
+
+    mod test_sample_sort_par {
+        use super::super::sample_sort_par;
+        use super::with_threads;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            sample_sort_par(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            sample_sort_par(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            sample_sort_par(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_random_input() {
+            for num_threads in [1, 3, 8] {
+                for length in [20_000, 100_000, 300_001] {
+                    let mut input: Vec<u64> = random(length, 42);
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    with_threads(num_threads, || sample_sort_par(&mut input));
+
+                    assert_eq!(
+                        expected, input,
+                        "{} threads, length {}",
+                        num_threads, length
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_few_distinct() {
+            let mut input: Vec<u64> = random::<u64>(200_000, 42).iter().map(|x| x % 3).collect();
+            let mut expected = input.clone();
+            expected.sort();
+
+            with_threads(4, || sample_sort_par(&mut input));
+
+            assert_eq!(expected, input);
+        }
+
+        #[test]
+        fn test_sorted_and_reversed() {
+            let mut input: Vec<u64> = (0..100_000).collect();
+            let expected = input.clone();
+
+            sample_sort_par(&mut input);
+
+            assert_eq!(expected, input);
+
+            input.reverse();
+
+            sample_sort_par(&mut input);
+
+            assert_eq!(expected, input);
+        }
+
+        #[test]
+        fn test_large_array() {
+            let mut input = vec![0; 40_000_000];
+            input[0] = 10;
+
+            sample_sort_par(&mut input);
+
+            assert_eq!(10, input[input.len() - 1]);
+        }
+    }
+
+    mod test_sample_sort_par_by {
+        use super::super::{sample_sort_par_by, sample_sort_par_by_key};
+        use crate::test_util::random;
+
+        #[test]
+        fn test_descending() {
+            let mut input: Vec<u64> = random(50_000, 42);
+            let mut expected = input.clone();
+            expected.sort_by(|a, b| b.cmp(a));
+
+            sample_sort_par_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(expected, input);
+        }
+
+        #[test]
+        fn test_by_key() {
+            let mut input: Vec<(u64, u64)> = random::<u64>(50_000, 42)
+                .iter()
+                .map(|&x| (x % 1_000, x))
+                .collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| p.0);
+
+            sample_sort_par_by_key(&mut input, |p| p.0);
+
+            let keys = |v: &[(u64, u64)]| v.iter().map(|p| p.0).collect::<Vec<_>>();
+            assert_eq!(keys(&expected), keys(&input));
+        }
+    }
+
+    mod test_distribute {
+        use super::super::{distribute, pick_splitters};
+        use super::with_threads;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_balanced_buckets() {
+            let length = 1 << 20;
+            let buckets = 64;
+            let mut one_distinct = vec![0u64; length];
+            one_distinct[0] = 10;
+            let inputs = [
+                ("one distinct", one_distinct),
+                (
+                    "few distinct",
+                    random::<u64>(length, 42).iter().map(|x| x % 3).collect(),
+                ),
+                ("random", random(length, 42)),
+            ];
+
+            for (name, mut input) in inputs {
+                let splitters = pick_splitters(&input, buckets, &u64::cmp);
+                let bounds = with_threads(4, || distribute(&mut input, &splitters, &u64::cmp));
+
+                // The buckets that still have to be sorted are the even ones.
+                let largest = bounds
+                    .windows(2)
+                    .step_by(2)
+                    .map(|w| w[1] - w[0])
+                    .max()
+                    .unwrap();
+                assert!(largest <= 2 * length / buckets, "{}: {}", name, largest);
+                for (b, w) in bounds.windows(2).enumerate().skip(1).step_by(2) {
+                    let splitter = splitters[b / 2];
+                    assert!(input[w[0]..w[1]].iter().all(|&x| x == splitter), "{}", name);
+                }
+                assert_eq!(length, bounds[bounds.len() - 1]);
+            }
+        }
+
+        #[test]
+        fn test_repeated_splitters() {
+            let input = [5, 5, 5, 5, 1, 5, 5, 5, 9, 5];
+
+            let splitters = pick_splitters(&input, 4, &i32::cmp);
+
+            assert!(splitters.windows(2).all(|w| w[0] < w[1]), "{:?}", splitters);
+        }
+    }
+
+    mod test_par_distribute {
+        use super::super::par_distribute;
+        use super::with_threads;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_groups_stably() {
+            let mut input: Vec<(u64, usize)> = random::<u64>(50_000, 42)
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| (x % 10, i))
+                .collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| p.0);
+            let mut work = input.clone();
+
+            let bounds = with_threads(4, || {
+                par_distribute(&mut input, &mut work, 10, |p| p.0 as usize)
+            });
+
+            assert_eq!(expected, input);
+            assert_eq!(11, bounds.len());
+            for b in 0..10 {
+                assert!(input[bounds[b]..bounds[b + 1]]
+                    .iter()
+                    .all(|p| p.0 == b as u64));
+            }
+            assert_eq!(input.len(), bounds[10]);
+        }
+
+        #[test]
+        fn test_empty_buckets() {
+            let mut input = vec![7usize; 10_000];
+            let mut work = vec![0; 10_000];
+
+            let bounds = par_distribute(&mut input, &mut work, 9, |&x| x);
+
+            assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 10_000, 10_000], bounds);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt;
+
+use crate::bucket_sort::bucket_sort;
+use crate::counting_sort::auto_counting_sort;
+use crate::heap_sort::heap_sort;
+use crate::merge_sort::{
+    bottom_up_merge_sort, in_place_merge_sort, merge_sort, natural_merge_sort,
+    top_down_merge_sort_par,
+};
+use crate::quick_sort::{intro_sort, pdq_sort, quick_sort};
+use crate::radix_sort::{msd_radix_sort, radix_sort, radix_sort_par, RadixKey};
+use crate::sample_sort::sample_sort_par;
+use crate::tim_sort::tim_sort;
+
+// A sorting algorithm that can be picked at runtime, e.g. by name from a Registry.
+//
+// Every sort in the crate that sorts a whole slice by the element order has one, as a unit
+// struct named after the function it calls. Sorts that allocate their work buffer here do so
+// on every call. Sorting networks (fixed-size arrays only) and ExternalSorter (readers and
+// files) don't fit the signature and have none.
+pub trait Sorter<T> {
+    // The name of the function the sorter calls; unique within the crate.
+    fn name(&self) -> &'static str;
+
+    // Whether elements that compare Equal keep their original relative order.
+    fn is_stable(&self) -> bool;
+
+    // Whether the sort needs no extra memory proportional to the input length.
+    fn is_in_place(&self) -> bool;
+
+    fn sort(&self, input: &mut [T]);
+}
+
+macro_rules! sorters {
+    ($($sorter:ident: $name:literal, stable: $stable:literal, in_place: $in_place:literal,
+       T: [$($bound:tt)+] => $sort:expr;)*) => {$(
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $sorter;
+
+        impl<T: $($bound)+> Sorter<T> for $sorter {
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            fn is_stable(&self) -> bool {
+                $stable
+            }
+
+            fn is_in_place(&self) -> bool {
+                $in_place
+            }
+
+            fn sort(&self, input: &mut [T]) {
+                ($sort)(input)
+            }
+        }
+    )*};
+}
+
+sorters! {
+    TopDownMergeSort: "top_down_merge_sort", stable: true, in_place: false,
+        T: [Ord + Copy] => merge_sort;
+    TopDownMergeSortPar: "top_down_merge_sort_par", stable: true, in_place: false,
+        T: [Ord + Copy + Send + Sync] => |input: &mut [T]| {
+            let mut work = input.to_vec();
+            top_down_merge_sort_par(input, &mut work)
+        };
+    BottomUpMergeSort: "bottom_up_merge_sort", stable: true, in_place: false,
+        T: [Ord + Copy] => |input: &mut [T]| {
+            let mut work = input.to_vec();
+            bottom_up_merge_sort(input, &mut work)
+        };
+    NaturalMergeSort: "natural_merge_sort", stable: true, in_place: false,
+        T: [Ord + Copy] => |input: &mut [T]| {
+            let mut work = input.to_vec();
+            natural_merge_sort(input, &mut work)
+        };
+    InPlaceMergeSort: "in_place_merge_sort", stable: true, in_place: true,
+        T: [Ord] => in_place_merge_sort;
+    TimSort: "tim_sort", stable: true, in_place: false,
+        T: [Ord + Copy] => tim_sort;
+    QuickSort: "quick_sort", stable: false, in_place: true,
+        T: [Ord] => quick_sort;
+    IntroSort: "intro_sort", stable: false, in_place: true,
+        T: [Ord] => intro_sort;
+    PdqSort: "pdq_sort", stable: false, in_place: true,
+        T: [Ord] => pdq_sort;
+    HeapSort: "heap_sort", stable: false, in_place: true,
+        T: [Ord] => heap_sort;
+    SampleSortPar: "sample_sort_par", stable: false, in_place: false,
+        T: [Ord + Copy + Send + Sync] => sample_sort_par;
+    RadixSort: "radix_sort", stable: true, in_place: false,
+        T: [RadixKey] => radix_sort;
+    RadixSortPar: "radix_sort_par", stable: true, in_place: false,
+        T: [RadixKey + Send + Sync] => radix_sort_par;
+    MsdRadixSort: "msd_radix_sort", stable: false, in_place: true,
+        T: [AsRef<[u8]>] => msd_radix_sort;
+    AutoCountingSort: "auto_counting_sort", stable: true, in_place: false,
+        T: [Copy + Into<i64>] => auto_counting_sort;
+}
+
+// bucket_sort only sorts f64s.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BucketSort;
+
+impl Sorter<f64> for BucketSort {
+    fn name(&self) -> &'static str {
+        "bucket_sort"
+    }
+
+    fn is_stable(&self) -> bool {
+        true
+    }
+
+    fn is_in_place(&self) -> bool {
+        false
+    }
+
+    fn sort(&self, input: &mut [f64]) {
+        bucket_sort(input)
+    }
+}
+
+// Sorters for one element type, in registration order, looked up by name.
+pub struct Registry<T> {
+    sorters: Vec<Box<dyn Sorter<T>>>,
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Registry {
+            sorters: Vec::new(),
+        }
+    }
+
+    // Panics if a sorter with the same name is already registered.
+    pub fn register<S: Sorter<T> + 'static>(&mut self, sorter: S) -> &mut Self {
+        assert!(
+            self.get(sorter.name()).is_none(),
+            "a sorter named {} is already registered",
+            sorter.name()
+        );
+        self.sorters.push(Box::new(sorter));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Sorter<T>> {
+        self.iter().find(|sorter| sorter.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Sorter<T>> {
+        self.sorters.iter().map(|sorter| sorter.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.iter().map(|sorter| sorter.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorters.is_empty()
+    }
+}
+
+impl<T: Ord + Copy + Send + Sync> Registry<T> {
+    // Every comparison sort in the crate.
+    pub fn comparison_sorts() -> Self {
+        let mut registry = Registry::new();
+        registry
+            .register(TopDownMergeSort)
+            .register(TopDownMergeSortPar)
+            .register(BottomUpMergeSort)
+            .register(NaturalMergeSort)
+            .register(InPlaceMergeSort)
+            .register(TimSort)
+            .register(QuickSort)
+            .register(IntroSort)
+            .register(PdqSort)
+            .register(HeapSort)
+            .register(SampleSortPar);
+        registry
+    }
+}
+
+impl<T: Ord + Copy + Send + Sync + RadixKey + Into<i64>> Registry<T> {
+    // The comparison sorts plus the radix and counting sorts, for the integer types that
+    // convert into i64.
+    pub fn integer_sorts() -> Self {
+        let mut registry = Self::comparison_sorts();
+        registry
+            .register(RadixSort)
+            .register(RadixSortPar)
+            .register(AutoCountingSort);
+        registry
+    }
+}
+
+impl Registry<f64> {
+    // f64 has no Ord, so only the sorts that order by total_cmp.
+    pub fn float_sorts() -> Self {
+        let mut registry = Registry::new();
+        registry
+            .register(RadixSort)
+            .register(RadixSortPar)
+            .register(BucketSort);
+        registry
+    }
+}
+
+impl<T: Ord + AsRef<[u8]>> Registry<T> {
+    // The sorts that don't copy elements, plus msd_radix_sort, for String and Vec<u8>. &str
+    // and &[u8] are Copy, so they can use comparison_sorts with MsdRadixSort registered too.
+    pub fn byte_string_sorts() -> Self {
+        let mut registry = Registry::new();
+        registry
+            .register(InPlaceMergeSort)
+            .register(QuickSort)
+            .register(IntroSort)
+            .register(PdqSort)
+            .register(HeapSort)
+            .register(MsdRadixSort);
+        registry
+    }
+}
+
+impl<T> fmt::Debug for Registry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test_sorter {
+    fn inputs() -> Vec<Vec<i32>> {
+        let mut inputs = vec![
+            vec![],
+            vec![1],
+            vec![2, 1, 10, 4, 4, 3, 7, 5],
+            vec![8, 7, 6, 5, 4, 3, 2, 1],
+            vec![i32::MAX, -3, 0, i32::MIN, 7, -3],
+        ];
+        inputs.push((0..5_000).map(|i| (i * 7919) % 1_009 - 500).collect());
+        inputs.push((0..70_000).map(|i| (i * 7919) % 65_521).collect());
+        inputs
+    }
+
+    mod test_registry {
+        use super::super::{Registry, Sorter};
+        use super::inputs;
+        use crate::test_util::{ids, Record};
+
+        #[test]
+        fn test_every_integer_sort() {
+            let registry = Registry::<i32>::integer_sorts();
+            for sorter in registry.iter() {
+                for input in inputs() {
+                    let mut expected = input.clone();
+                    expected.sort();
+                    let mut input = input;
+
+                    sorter.sort(&mut input);
+
+                    assert_eq!(expected, input, "{}", sorter.name());
+                }
+            }
+        }
+
+        #[test]
+        fn test_stable_flags() {
+            let registry = Registry::<Record>::comparison_sorts();
+            let mut input: Vec<Record> = (0..1_000)
+                .map(|id| Record {
+                    key: (id as u32 * 7919) % 7,
+                    id,
+                })
+                .collect();
+            let mut expected = input.clone();
+            expected.sort();
+
+            for sorter in registry.iter().filter(|sorter| sorter.is_stable()) {
+                let mut sorted = input.clone();
+
+                sorter.sort(&mut sorted);
+
+                assert_eq!(ids(&expected), ids(&sorted), "{}", sorter.name());
+            }
+
+            // Unstable sorts do reorder equal elements here, so the test above would catch a
+            // sorter that is marked stable by mistake.
+            super::super::QuickSort.sort(&mut input);
+            assert_ne!(ids(&expected), ids(&input));
+        }
+
+        #[test]
+        fn test_float_sorts() {
+            let registry = Registry::float_sorts();
+            let input = [3.5, -0.0, f64::NAN, f64::NEG_INFINITY, 0.0, -2.25, 1e300];
+            let mut expected = input;
+            expected.sort_by(f64::total_cmp);
+            let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+
+            for sorter in registry.iter() {
+                let mut sorted = input;
+
+                sorter.sort(&mut sorted);
+
+                assert_eq!(bits(&expected), bits(&sorted), "{}", sorter.name());
+            }
+        }
+
+        #[test]
+        fn test_byte_string_sorts() {
+            let registry = Registry::<String>::byte_string_sorts();
+            let input: Vec<String> = (0..500).map(|i| format!("{}", (i * 7919) % 613)).collect();
+            let mut expected = input.clone();
+            expected.sort();
+
+            for sorter in registry.iter() {
+                let mut sorted = input.clone();
+
+                sorter.sort(&mut sorted);
+
+                assert_eq!(expected, sorted, "{}", sorter.name());
+            }
+        }
+
+        #[test]
+        fn test_get_by_name() {
+            let registry = Registry::<u8>::integer_sorts();
+
+            let sorter = registry.get("radix_sort").unwrap();
+            assert!(sorter.is_stable());
+            assert!(!sorter.is_in_place());
+
+            let sorter = registry.get("heap_sort").unwrap();
+            assert!(!sorter.is_stable());
+            assert!(sorter.is_in_place());
+
+            assert!(registry.get("bogo_sort").is_none());
+        }
+
+        #[test]
+        fn test_names() {
+            let registry = Registry::<i64>::integer_sorts();
+
+            assert_eq!(14, registry.len());
+            assert_eq!(
+                vec![
+                    "top_down_merge_sort",
+                    "top_down_merge_sort_par",
+                    "bottom_up_merge_sort",
+                    "natural_merge_sort",
+                    "in_place_merge_sort",
+                    "tim_sort",
+                    "quick_sort",
+                    "intro_sort",
+                    "pdq_sort",
+                    "heap_sort",
+                    "sample_sort_par",
+                    "radix_sort",
+                    "radix_sort_par",
+                    "auto_counting_sort",
+                ],
+                registry.names()
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "a sorter named pdq_sort is already registered")]
+        fn test_duplicate_name() {
+            Registry::<i32>::comparison_sorts().register(super::super::PdqSort);
+        }
+    }
+}
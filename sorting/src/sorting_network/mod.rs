@@ -0,0 +1,456 @@
+use std::cmp::Ordering;
+
+// The longest input with a stored network.
+pub const MAX_NETWORK_LEN: usize = 16;
+
+// Sorting networks: fixed sequences of compare-exchange steps (i, j), i < j, each of which puts
+// the smaller of input[i] and input[j] at i. The sequence doesn't depend on the data, so there
+// is no branching on where to go next, only on whether to swap.
+// https://en.wikipedia.org/wiki/Sorting_network
+//
+// These are the smallest known networks for every length, listed by Bert Dobbelaere; all but
+// the ones for 13 to 16 inputs are proven optimal. The 15 input network is the 16 input one
+// with the last wire and the comparators on it removed. Each line is one layer of comparators
+// on disjoint wires.
+// https://bertdobbelaere.github.io/sorting_networks.html
+#[rustfmt::skip]
+const NETWORKS: [&[(usize, usize)]; MAX_NETWORK_LEN + 1] = [
+    &[],
+    &[],
+    // 2 inputs: 1 comparator in 1 layer
+    &[
+        (0, 1),
+    ],
+    // 3 inputs: 3 comparators in 3 layers
+    &[
+        (0, 2),
+        (0, 1),
+        (1, 2),
+    ],
+    // 4 inputs: 5 comparators in 3 layers
+    &[
+        (0, 2), (1, 3),
+        (0, 1), (2, 3),
+        (1, 2),
+    ],
+    // 5 inputs: 9 comparators in 5 layers
+    &[
+        (0, 3), (1, 4),
+        (0, 2), (1, 3),
+        (0, 1), (2, 4),
+        (1, 2), (3, 4),
+        (2, 3),
+    ],
+    // 6 inputs: 12 comparators in 5 layers
+    &[
+        (0, 5), (1, 3), (2, 4),
+        (1, 2), (3, 4),
+        (0, 3), (2, 5),
+        (0, 1), (2, 3), (4, 5),
+        (1, 2), (3, 4),
+    ],
+    // 7 inputs: 16 comparators in 6 layers
+    &[
+        (0, 6), (2, 3), (4, 5),
+        (0, 2), (1, 4), (3, 6),
+        (0, 1), (2, 5), (3, 4),
+        (1, 2), (4, 6),
+        (2, 3), (4, 5),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 8 inputs: 19 comparators in 6 layers
+    &[
+        (0, 2), (1, 3), (4, 6), (5, 7),
+        (0, 4), (1, 5), (2, 6), (3, 7),
+        (0, 1), (2, 3), (4, 5), (6, 7),
+        (2, 4), (3, 5),
+        (1, 4), (3, 6),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 9 inputs: 25 comparators in 7 layers
+    &[
+        (0, 3), (1, 7), (2, 5), (4, 8),
+        (0, 7), (2, 4), (3, 8), (5, 6),
+        (0, 2), (1, 3), (4, 5), (7, 8),
+        (1, 4), (3, 6), (5, 7),
+        (0, 1), (2, 4), (3, 5), (6, 8),
+        (2, 3), (4, 5), (6, 7),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    // 10 inputs: 29 comparators in 8 layers
+    &[
+        (0, 8), (1, 9), (2, 7), (3, 5), (4, 6),
+        (0, 2), (1, 4), (5, 8), (7, 9),
+        (0, 3), (2, 4), (5, 7), (6, 9),
+        (0, 1), (3, 6), (8, 9),
+        (1, 5), (2, 3), (4, 8), (6, 7),
+        (1, 2), (3, 5), (4, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+        (3, 4), (5, 6),
+    ],
+    // 11 inputs: 35 comparators in 8 layers
+    &[
+        (0, 9), (1, 6), (2, 4), (3, 7), (5, 8),
+        (0, 1), (3, 5), (4, 10), (6, 9), (7, 8),
+        (1, 3), (2, 5), (4, 7), (8, 10),
+        (0, 4), (1, 2), (3, 7), (5, 9), (6, 8),
+        (0, 1), (2, 6), (4, 5), (7, 8), (9, 10),
+        (2, 4), (3, 6), (5, 7), (8, 9),
+        (1, 2), (3, 4), (5, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+    ],
+    // 12 inputs: 39 comparators in 9 layers
+    &[
+        (0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9),
+        (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11),
+        (0, 2), (1, 6), (5, 10), (9, 11),
+        (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10),
+        (1, 4), (3, 5), (6, 8), (7, 10),
+        (1, 3), (2, 5), (6, 9), (8, 10),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (4, 6), (5, 7),
+        (3, 4), (5, 6), (7, 8),
+    ],
+    // 13 inputs: 45 comparators in 10 layers
+    &[
+        (0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8),
+        (1, 6), (2, 3), (4, 11), (7, 9), (8, 10),
+        (0, 4), (1, 2), (3, 6), (7, 8), (9, 10), (11, 12),
+        (4, 6), (5, 9), (8, 11), (10, 12),
+        (0, 5), (3, 8), (4, 7), (6, 11), (9, 10),
+        (0, 1), (2, 5), (6, 9), (7, 8), (10, 11),
+        (1, 3), (2, 4), (5, 6), (9, 10),
+        (1, 2), (3, 4), (5, 7), (6, 8),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (3, 4), (5, 6),
+    ],
+    // 14 inputs: 51 comparators in 10 layers
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 8), (5, 9), (10, 12), (11, 13),
+        (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13), (11, 12),
+        (0, 6), (1, 5), (3, 9), (4, 10), (7, 13), (8, 12),
+        (2, 10), (3, 11), (4, 6), (7, 9),
+        (1, 3), (2, 8), (5, 11), (6, 7), (10, 12),
+        (1, 4), (2, 6), (3, 5), (7, 11), (8, 10), (9, 12),
+        (2, 4), (3, 6), (5, 8), (7, 10), (9, 11),
+        (3, 4), (5, 6), (7, 8), (9, 10),
+        (6, 7),
+    ],
+    // 15 inputs: 56 comparators in 10 layers
+    &[
+        (0, 13), (1, 12), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10),
+        (0, 5), (1, 7), (2, 9), (3, 4), (6, 13), (8, 14), (11, 12),
+        (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14),
+        (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14),
+        (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (2, 4), (3, 6), (9, 12), (11, 13),
+        (3, 5), (6, 8), (7, 9), (10, 12),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+    // 16 inputs: 60 comparators in 10 layers
+    &[
+        (0, 13), (1, 12), (2, 15), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10),
+        (0, 5), (1, 7), (2, 9), (3, 4), (6, 13), (8, 14), (10, 15), (11, 12),
+        (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13), (14, 15),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (13, 15),
+        (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14),
+        (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (2, 4), (3, 6), (9, 12), (11, 13),
+        (3, 5), (6, 8), (7, 9), (10, 12),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+];
+
+// Returns the stored network for n inputs.
+//
+// Panics if n is greater than MAX_NETWORK_LEN.
+pub fn network(n: usize) -> &'static [(usize, usize)] {
+    assert!(
+        n <= MAX_NETWORK_LEN,
+        "no sorting network is stored for {} inputs",
+        n
+    );
+    NETWORKS[n]
+}
+
+// Sorts a fixed-size array with the stored network for its length. Arrays longer than
+// MAX_NETWORK_LEN don't compile. Not stable.
+pub fn network_sort<T: Ord, const N: usize>(input: &mut [T; N]) {
+    network_sort_by(input, T::cmp);
+}
+
+// Same as network_sort, but orders elements using the comparator function.
+pub fn network_sort_by<T, F, const N: usize>(input: &mut [T; N], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    const {
+        assert!(
+            N <= MAX_NETWORK_LEN,
+            "no sorting network is stored for N inputs"
+        )
+    };
+    apply_network_by(input, NETWORKS[N], compare);
+}
+
+// Same as network_sort, but orders elements by the key extracted with f.
+pub fn network_sort_by_key<T, K, F, const N: usize>(input: &mut [T; N], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    network_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Runs the compare-exchange steps of network over input, e.g. one from batcher_network.
+//
+// Panics if network refers to an index past the end of input.
+pub fn apply_network_by<T, F>(input: &mut [T], network: &[(usize, usize)], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for &(i, j) in network {
+        if compare(&input[j], &input[i]) == Ordering::Less {
+            input.swap(i, j);
+        }
+    }
+}
+
+// Batcher's odd-even merge sort as a network for n inputs: sorts both halves recursively, then
+// merges them by merging the even and the odd positions separately. Takes
+// O(n log^2 n) comparators, 63 for 16 inputs.
+// https://en.wikipedia.org/wiki/Batcher_odd%E2%80%93even_mergesort
+//
+// Panics if n is not a power of two.
+pub fn batcher_network(n: usize) -> Vec<(usize, usize)> {
+    assert!(n.is_power_of_two(), "{} is not a power of two", n);
+    let mut network = Vec::new();
+    let mut p = 1;
+    while p < n {
+        let mut k = p;
+        while k >= 1 {
+            for j in (k % p..n - k).step_by(2 * k) {
+                for i in 0..k.min(n - j - k) {
+                    // Only compare within the same pair of runs being merged.
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                        network.push((i + j, i + j + k));
+                    }
+                }
+            }
+            k /= 2;
+        }
+        p *= 2;
+    }
+    network
+}
+
+// Bitonic sort as a network for n inputs: sorts both halves recursively, then merges them by
+// comparing the halves mirrored (which is the same as merging the first half with the second
+// one reversed), then halving the distance between compared wires down to 1. Takes
+// n/4 log n (log n + 1) comparators, 80 for 16 inputs, but every layer compares all wires at
+// the same distance, which suits SIMD and GPUs.
+// https://en.wikipedia.org/wiki/Bitonic_sorter
+//
+// Panics if n is not a power of two.
+pub fn bitonic_network(n: usize) -> Vec<(usize, usize)> {
+    assert!(n.is_power_of_two(), "{} is not a power of two", n);
+    let mut network = Vec::new();
+    let mut k = 2;
+    while k <= n {
+        network.extend((0..n).map(|i| (i, i ^ (k - 1))).filter(|&(i, l)| i < l));
+        let mut j = k / 4;
+        while j > 0 {
+            network.extend((0..n).map(|i| (i, i ^ j)).filter(|&(i, l)| i < l));
+            j /= 2;
+        }
+        k *= 2;
+    }
+    network
+}
+
+#[cfg(test)]
+mod test_sorting_network {
+    // By the 0-1 principle a network sorts every input if it sorts every input of 0s and 1s.
+    // Runs the network over all 2^n of them, as bit masks with wire i in bit i.
+    fn sorts_all_zero_one_inputs(n: usize, network: &[(usize, usize)]) -> bool {
+        (0u32..1 << n).all(|mut bits| {
+            for &(i, j) in network {
+                if bits >> i & 1 == 1 && bits >> j & 1 == 0 {
+                    bits ^= 1 << i | 1 << j;
+                }
+            }
+            // Sorted means all the 1s are on the highest wires.
+            let ones = bits.count_ones() as usize;
+            bits == ((1 << n) - 1) ^ ((1 << (n - ones)) - 1)
+        })
+    }
+
+    mod test_network {
+        use super::super::{network, MAX_NETWORK_LEN};
+        use super::sorts_all_zero_one_inputs;
+
+        #[test]
+        fn test_every_network_sorts() {
+            for n in 0..=MAX_NETWORK_LEN {
+                assert!(sorts_all_zero_one_inputs(n, network(n)), "{} inputs", n);
+            }
+        }
+
+        #[test]
+        fn test_best_known_sizes() {
+            let sizes = [0, 0, 1, 3, 5, 9, 12, 16, 19, 25, 29, 35, 39, 45, 51, 56, 60];
+            for (n, &size) in sizes.iter().enumerate() {
+                assert_eq!(size, network(n).len(), "{} inputs", n);
+            }
+        }
+
+        #[test]
+        fn test_comparators_are_ordered_and_in_range() {
+            for n in 0..=MAX_NETWORK_LEN {
+                assert!(
+                    network(n).iter().all(|&(i, j)| i < j && j < n),
+                    "{} inputs",
+                    n
+                );
+            }
+        }
+
+        #[test]
+        fn test_detects_broken_network() {
+            let mut broken = network(8).to_vec();
+            broken.pop();
+
+            assert!(!sorts_all_zero_one_inputs(8, &broken));
+        }
+
+        #[test]
+        #[should_panic(expected = "no sorting network is stored for 17 inputs")]
+        fn test_too_many_inputs() {
+            network(17);
+        }
+    }
+
+    mod test_network_sort {
+        use super::super::{network_sort, network_sort_by, network_sort_by_key};
+        use crate::test_util::random;
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            network_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            network_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            network_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+
+            network_sort(&mut input);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_random_arrays() {
+            for seed in 0..100 {
+                let mut input: [u64; 16] = random(16, seed).try_into().unwrap();
+                let mut expected = input;
+                expected.sort();
+
+                network_sort(&mut input);
+
+                assert_eq!(expected, input, "seed {}", seed);
+            }
+        }
+
+        #[test]
+        fn test_by() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5, 9, 0, 6];
+
+            network_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 9, 7, 6, 5, 4, 4, 3, 2, 1, 0], &input);
+
+            network_sort_by_key(&mut input, |&x| x % 3);
+
+            assert!(input.windows(2).all(|w| w[0] % 3 <= w[1] % 3));
+        }
+    }
+
+    mod test_generators {
+        use super::super::{apply_network_by, batcher_network, bitonic_network};
+        use super::sorts_all_zero_one_inputs;
+        use crate::test_util::random;
+
+        #[test]
+        fn test_batcher_sorts() {
+            for n in [1, 2, 4, 8, 16] {
+                assert!(
+                    sorts_all_zero_one_inputs(n, &batcher_network(n)),
+                    "{} inputs",
+                    n
+                );
+            }
+            assert_eq!(63, batcher_network(16).len());
+        }
+
+        #[test]
+        fn test_bitonic_sorts() {
+            for n in [1, 2, 4, 8, 16] {
+                assert!(
+                    sorts_all_zero_one_inputs(n, &bitonic_network(n)),
+                    "{} inputs",
+                    n
+                );
+            }
+            assert_eq!(80, bitonic_network(16).len());
+        }
+
+        #[test]
+        fn test_large_networks() {
+            for n in [32, 64, 128] {
+                for seed in 0..20 {
+                    let mut expected: Vec<u64> = random(n, seed);
+                    expected.sort();
+                    for network in [batcher_network(n), bitonic_network(n)] {
+                        let mut input = random(n, seed);
+
+                        apply_network_by(&mut input, &network, u64::cmp);
+
+                        assert_eq!(expected, input, "{} inputs, seed {}", n, seed);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "12 is not a power of two")]
+        fn test_not_power_of_two() {
+            batcher_network(12);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Helpers shared by the tests of the sorts.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+// Linear congruential generator with Knuth's MMIX constants, so failures can be reproduced.
+// Yields its whole 64-bit state; the low bits of an LCG are far from random, so take values
+// from the high bits, as random does.
+#[derive(Debug, Clone)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg { state: seed }
+    }
+}
+
+impl Iterator for Lcg {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        Some(self.state)
+    }
+}
+
+// length pseudo-random values of 31 bits, which fit any integer type from i32 up.
+pub fn random<T>(length: usize, seed: u64) -> Vec<T>
+where
+    T: TryFrom<u64>,
+    T::Error: Debug,
+{
+    random_below(length, u64::MAX, seed)
+}
+
+// Same as random, but every value is below bound.
+pub fn random_below<T>(length: usize, bound: u64, seed: u64) -> Vec<T>
+where
+    T: TryFrom<u64>,
+    T::Error: Debug,
+{
+    Lcg::new(seed)
+        .take(length)
+        .map(|state| T::try_from((state >> 33) % bound).unwrap())
+        .collect()
+}
+
+// Orders only by key, so records with equal keys are indistinguishable to the sort and the id
+// records the original position for checking stability.
+#[derive(Clone, Copy, Debug)]
+pub struct Record {
+    pub key: u32,
+    pub id: usize,
+}
+
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Record {}
+
+impl PartialOrd for Record {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Record {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+pub fn ids(records: &[Record]) -> Vec<usize> {
+    records.iter().map(|r| r.id).collect()
+}
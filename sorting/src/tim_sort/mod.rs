@@ -0,0 +1,658 @@
+use std::cmp::{self, Ordering};
+
+// Inputs shorter than this are sorted with a single binary insertion sort.
+const MIN_MERGE: usize = 32;
+
+// Number of consecutive wins by one run before a merge switches to galloping mode.
+const MIN_GALLOP: usize = 7;
+
+// https://en.wikipedia.org/wiki/Timsort
+// https://github.com/python/cpython/blob/main/Objects/listsort.txt
+// Stable; allocates a buffer of at most input.len() / 2 elements.
+pub fn tim_sort<T: Ord + Copy>(input: &mut [T]) {
+    tim_sort_by(input, T::cmp);
+}
+
+// Same as tim_sort, but orders elements using the comparator function.
+// Elements that compare Equal keep their original relative order.
+pub fn tim_sort_by<T, F>(input: &mut [T], mut compare: F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let length = input.len();
+    if length < 2 {
+        return;
+    }
+
+    if length < MIN_MERGE {
+        let run_len = count_run_and_make_ascending(input, &mut compare);
+        binary_insertion_sort(input, run_len, &mut compare);
+        return;
+    }
+
+    let mut state = TimSort::new();
+    let min_run = min_run_length(length);
+    let mut start_idx = 0;
+    while start_idx < length {
+        // Extend short natural runs to min_run elements with binary insertion sort.
+        let mut run_len = count_run_and_make_ascending(&mut input[start_idx..], &mut compare);
+        if run_len < min_run {
+            let forced_len = cmp::min(min_run, length - start_idx);
+            binary_insertion_sort(
+                &mut input[start_idx..start_idx + forced_len],
+                run_len,
+                &mut compare,
+            );
+            run_len = forced_len;
+        }
+
+        state.runs.push(Run {
+            start_idx,
+            len: run_len,
+        });
+        state.merge_collapse(input, &mut compare);
+        start_idx += run_len;
+    }
+
+    state.merge_force_collapse(input, &mut compare);
+}
+
+// Same as tim_sort, but orders elements by the key extracted with f.
+pub fn tim_sort_by_key<T, K, F>(input: &mut [T], mut f: F)
+where
+    T: Copy,
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    tim_sort_by(input, |a, b| f(a).cmp(&f(b)));
+}
+
+// Picks a run length in [MIN_MERGE / 2, MIN_MERGE] such that length / min_run is a power of
+// two or slightly less than one, which keeps the final merges balanced.
+fn min_run_length(mut length: usize) -> usize {
+    // becomes 1 if any of the shifted off bits is set
+    let mut r = 0;
+    while length >= MIN_MERGE {
+        r |= length & 1;
+        length >>= 1;
+    }
+    length + r
+}
+
+// Returns the length of the run at the start of input. A strictly descending run is reversed
+// so that every run ends up ascending; it has to be strict to keep the sort stable.
+fn count_run_and_make_ascending<T, F>(input: &mut [T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let length = input.len();
+    if length < 2 {
+        return length;
+    }
+
+    let mut end_idx = 2;
+    if compare(&input[1], &input[0]) == Ordering::Less {
+        while end_idx < length && compare(&input[end_idx], &input[end_idx - 1]) == Ordering::Less {
+            end_idx += 1;
+        }
+        input[..end_idx].reverse();
+    } else {
+        while end_idx < length && compare(&input[end_idx], &input[end_idx - 1]) != Ordering::Less {
+            end_idx += 1;
+        }
+    }
+
+    end_idx
+}
+
+// Sorts input given that input[..sorted_len] is already sorted. The insertion point is found
+// with a binary search, placing each element after any equal ones.
+fn binary_insertion_sort<T, F>(input: &mut [T], sorted_len: usize, compare: &mut F)
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in cmp::max(sorted_len, 1)..input.len() {
+        let pivot = input[i];
+        let idx = input[..i].partition_point(|x| compare(&pivot, x) != Ordering::Less);
+        input.copy_within(idx..i, idx + 1);
+        input[idx] = pivot;
+    }
+}
+
+// Returns the number of leading elements of input for which before is true. input has to be
+// partitioned by before. The search starts at hint and probes at offsets 1, 3, 7, 15... before
+// finishing with a binary search, so it is fast when the answer is close to hint.
+fn gallop<T, P>(input: &[T], hint: usize, mut before: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let length = input.len();
+    if length == 0 {
+        return 0;
+    }
+
+    // The answer lies in [start_idx, end_idx].
+    let (start_idx, end_idx) = if before(&input[hint]) {
+        let max_offset = length - hint;
+        let mut last_offset = 0;
+        let mut offset = 1;
+        while offset < max_offset && before(&input[hint + offset]) {
+            last_offset = offset;
+            offset = (offset << 1) + 1;
+        }
+        (hint + last_offset + 1, hint + cmp::min(offset, max_offset))
+    } else {
+        let max_offset = hint + 1;
+        let mut last_offset = 0;
+        let mut offset = 1;
+        while offset < max_offset && !before(&input[hint - offset]) {
+            last_offset = offset;
+            offset = (offset << 1) + 1;
+        }
+        (hint + 1 - cmp::min(offset, max_offset), hint - last_offset)
+    };
+
+    start_idx + input[start_idx..end_idx].partition_point(before)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start_idx: usize,
+    len: usize,
+}
+
+struct TimSort<T> {
+    // Holds a copy of the shorter run while two runs are being merged.
+    buffer: Vec<T>,
+    // Pending runs, left to right. Lengths satisfy the invariants checked in merge_collapse.
+    runs: Vec<Run>,
+    // Adapts to the data: lowered while galloping pays off, raised when it doesn't.
+    min_gallop: usize,
+}
+
+impl<T: Copy> TimSort<T> {
+    fn new() -> Self {
+        TimSort {
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            min_gallop: MIN_GALLOP,
+        }
+    }
+
+    // Merges pending runs until, for the run lengths A, B, C, D at the top of the stack,
+    //   1. B > C + D, A > B + C
+    //   2. C > D
+    // hold. Checking the top four runs rather than three fixes the invariant bug found in
+    // the original implementation by de Gouw et al. in 2015.
+    fn merge_collapse<F>(&mut self, input: &mut [T], compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        while self.runs.len() > 1 {
+            let mut n = self.runs.len() - 2;
+            let len = |i: usize| self.runs[i].len;
+            if (n > 0 && len(n - 1) <= len(n) + len(n + 1))
+                || (n > 1 && len(n - 2) <= len(n - 1) + len(n))
+            {
+                if len(n - 1) < len(n + 1) {
+                    n -= 1;
+                }
+            } else if len(n) > len(n + 1) {
+                break;
+            }
+            self.merge_at(input, n, compare);
+        }
+    }
+
+    // Merges all pending runs into one, once the whole input has been scanned.
+    fn merge_force_collapse<F>(&mut self, input: &mut [T], compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        while self.runs.len() > 1 {
+            let mut n = self.runs.len() - 2;
+            if n > 0 && self.runs[n - 1].len < self.runs[n + 1].len {
+                n -= 1;
+            }
+            self.merge_at(input, n, compare);
+        }
+    }
+
+    // Merges runs[i] and runs[i + 1], which are adjacent in input.
+    fn merge_at<F>(&mut self, input: &mut [T], i: usize, compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let Run {
+            start_idx: mut start1,
+            len: mut len1,
+        } = self.runs[i];
+        let Run {
+            start_idx: start2,
+            len: mut len2,
+        } = self.runs[i + 1];
+
+        self.runs[i].len = len1 + len2;
+        self.runs.remove(i + 1);
+
+        // Elements at the start of run 1 that are <= the first element of run 2 are already in
+        // place, as are elements at the end of run 2 that are >= the last element of run 1.
+        let first2 = input[start2];
+        let skip = gallop(&input[start1..start1 + len1], 0, |x| {
+            compare(x, &first2) != Ordering::Greater
+        });
+        start1 += skip;
+        len1 -= skip;
+        if len1 == 0 {
+            return;
+        }
+
+        let last1 = input[start1 + len1 - 1];
+        len2 = gallop(&input[start2..start2 + len2], len2 - 1, |x| {
+            compare(x, &last1) == Ordering::Less
+        });
+        if len2 == 0 {
+            return;
+        }
+
+        if len1 <= len2 {
+            self.merge_lo(&mut input[start1..start2 + len2], len1, compare);
+        } else {
+            self.merge_hi(&mut input[start1..start2 + len2], len1, compare);
+        }
+    }
+
+    // Merges input[..len1] and input[len1..] front to back, buffering the left run.
+    // Ties are taken from the left run first, which is what keeps the sort stable.
+    fn merge_lo<F>(&mut self, input: &mut [T], len1: usize, compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&input[..len1]);
+        let left = &self.buffer;
+        let end2 = input.len();
+
+        let mut cursor1 = 0;
+        let mut cursor2 = len1;
+        let mut dest = 0;
+        let mut min_gallop = self.min_gallop;
+
+        'merge: loop {
+            // Take one element at a time until one run keeps winning.
+            let mut count1 = 0;
+            let mut count2 = 0;
+            loop {
+                if cursor1 == len1 || cursor2 == end2 {
+                    break 'merge;
+                }
+                if compare(&input[cursor2], &left[cursor1]) == Ordering::Less {
+                    input[dest] = input[cursor2];
+                    cursor2 += 1;
+                    count1 = 0;
+                    count2 += 1;
+                } else {
+                    input[dest] = left[cursor1];
+                    cursor1 += 1;
+                    count1 += 1;
+                    count2 = 0;
+                }
+                dest += 1;
+                if count1 >= min_gallop || count2 >= min_gallop {
+                    break;
+                }
+            }
+            if cursor1 == len1 || cursor2 == end2 {
+                break;
+            }
+
+            // Gallop: move whole blocks while either run keeps winning by MIN_GALLOP or more.
+            loop {
+                let head2 = input[cursor2];
+                let count1 = gallop(&left[cursor1..len1], 0, |x| {
+                    compare(x, &head2) != Ordering::Greater
+                });
+                input[dest..dest + count1].copy_from_slice(&left[cursor1..cursor1 + count1]);
+                dest += count1;
+                cursor1 += count1;
+                if cursor1 == len1 {
+                    break 'merge;
+                }
+
+                input[dest] = input[cursor2];
+                dest += 1;
+                cursor2 += 1;
+                if cursor2 == end2 {
+                    break 'merge;
+                }
+
+                let head1 = left[cursor1];
+                let count2 = gallop(&input[cursor2..end2], 0, |x| {
+                    compare(x, &head1) == Ordering::Less
+                });
+                input.copy_within(cursor2..cursor2 + count2, dest);
+                dest += count2;
+                cursor2 += count2;
+                if cursor2 == end2 {
+                    break 'merge;
+                }
+
+                input[dest] = left[cursor1];
+                dest += 1;
+                cursor1 += 1;
+                if cursor1 == len1 {
+                    break 'merge;
+                }
+
+                min_gallop = min_gallop.saturating_sub(1);
+                if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                    break;
+                }
+            }
+            // Penalize leaving galloping mode.
+            min_gallop += 2;
+        }
+
+        // Whatever is left of run 2 is already in place.
+        input[dest..dest + len1 - cursor1].copy_from_slice(&left[cursor1..len1]);
+        self.min_gallop = cmp::max(min_gallop, 1);
+    }
+
+    // Merges input[..len1] and input[len1..] back to front, buffering the right run.
+    // Ties are taken from the right run first, which is what keeps the sort stable.
+    fn merge_hi<F>(&mut self, input: &mut [T], len1: usize, compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&input[len1..]);
+        let right = &self.buffer;
+
+        // Cursors and dest are exclusive ends.
+        let mut cursor1 = len1;
+        let mut cursor2 = right.len();
+        let mut dest = input.len();
+        let mut min_gallop = self.min_gallop;
+
+        'merge: loop {
+            // Take one element at a time until one run keeps winning.
+            let mut count1 = 0;
+            let mut count2 = 0;
+            loop {
+                if cursor1 == 0 || cursor2 == 0 {
+                    break 'merge;
+                }
+                dest -= 1;
+                if compare(&right[cursor2 - 1], &input[cursor1 - 1]) == Ordering::Less {
+                    cursor1 -= 1;
+                    input[dest] = input[cursor1];
+                    count1 += 1;
+                    count2 = 0;
+                } else {
+                    cursor2 -= 1;
+                    input[dest] = right[cursor2];
+                    count1 = 0;
+                    count2 += 1;
+                }
+                if count1 >= min_gallop || count2 >= min_gallop {
+                    break;
+                }
+            }
+            if cursor1 == 0 || cursor2 == 0 {
+                break;
+            }
+
+            // Gallop: move whole blocks while either run keeps winning by MIN_GALLOP or more.
+            loop {
+                let tail2 = right[cursor2 - 1];
+                let keep = gallop(&input[..cursor1], cursor1 - 1, |x| {
+                    compare(x, &tail2) != Ordering::Greater
+                });
+                let count1 = cursor1 - keep;
+                dest -= count1;
+                cursor1 -= count1;
+                input.copy_within(cursor1..cursor1 + count1, dest);
+                if cursor1 == 0 {
+                    break 'merge;
+                }
+
+                dest -= 1;
+                cursor2 -= 1;
+                input[dest] = right[cursor2];
+                if cursor2 == 0 {
+                    break 'merge;
+                }
+
+                let tail1 = input[cursor1 - 1];
+                let keep = gallop(&right[..cursor2], cursor2 - 1, |x| {
+                    compare(x, &tail1) == Ordering::Less
+                });
+                let count2 = cursor2 - keep;
+                dest -= count2;
+                cursor2 -= count2;
+                input[dest..dest + count2].copy_from_slice(&right[cursor2..cursor2 + count2]);
+                if cursor2 == 0 {
+                    break 'merge;
+                }
+
+                dest -= 1;
+                cursor1 -= 1;
+                input[dest] = input[cursor1];
+                if cursor1 == 0 {
+                    break 'merge;
+                }
+
+                min_gallop = min_gallop.saturating_sub(1);
+                if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                    break;
+                }
+            }
+            // Penalize leaving galloping mode.
+            min_gallop += 2;
+        }
+
+        // Whatever is left of run 1 is already in place.
+        input[..cursor2].copy_from_slice(&right[..cursor2]);
+        self.min_gallop = cmp::max(min_gallop, 1);
+    }
+}
+
+#[cfg(test)]
+mod test_tim_sort {
+    fn sawtooth(length: usize, tooth: usize) -> Vec<u64> {
+        (0..length).map(|i| (i % tooth) as u64).collect()
+    }
+
+    mod test_sort {
+        use super::super::tim_sort;
+        use super::sawtooth;
+        use crate::test_util::{random, random_below};
+
+        #[test]
+        fn test_empty_array() {
+            let mut src: [i32; 0] = [];
+
+            tim_sort(&mut src);
+
+            assert!(src.is_empty())
+        }
+
+        #[test]
+        fn test_single_element_array() {
+            let mut src = [1];
+
+            tim_sort(&mut src);
+
+            assert_eq!(1, src[0]);
+        }
+
+        #[test]
+        fn test_unsorted_array() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            tim_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 4, 5, 7, 10], &input);
+        }
+
+        #[test]
+        fn test_reversed_array() {
+            let mut input = [8, 7, 6, 5, 4, 3, 2, 1];
+
+            tim_sort(&mut input);
+
+            assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], &input);
+        }
+
+        #[test]
+        fn test_str_array() {
+            let mut input = ["pear", "apple", "fig", "banana", "apple"];
+
+            tim_sort(&mut input);
+
+            assert_eq!(&["apple", "apple", "banana", "fig", "pear"], &input);
+        }
+
+        #[test]
+        fn test_patterns() {
+            for length in [31, 32, 33, 64, 100, 257, 1000, 5000] {
+                let inputs = [
+                    random(length, 1),
+                    random_below(length, 4, 2),
+                    (0..length as u64).collect(),
+                    (0..length as u64).rev().collect(),
+                    sawtooth(length, 7),
+                    sawtooth(length, 100),
+                ];
+                for mut input in inputs {
+                    let mut expected = input.clone();
+                    expected.sort();
+
+                    tim_sort(&mut input);
+
+                    assert_eq!(expected, input);
+                }
+            }
+        }
+
+        #[test]
+        fn test_every_length() {
+            for length in 0..200 {
+                let mut input: Vec<u64> = random_below(length, 50, length as u64);
+                let mut expected = input.clone();
+                expected.sort();
+
+                tim_sort(&mut input);
+
+                assert_eq!(expected, input);
+            }
+        }
+
+        #[test]
+        fn test_galloping() {
+            // Interleaved blocks of very different sizes make one run win many times in a row.
+            let mut input: Vec<u64> = (0..2000).collect();
+            input.extend((0..40).map(|i| i * 50));
+            input.extend(1000..3000);
+            let mut expected = input.clone();
+            expected.sort();
+
+            tim_sort(&mut input);
+
+            assert_eq!(expected, input);
+        }
+    }
+
+    mod test_sort_by {
+        use super::super::{tim_sort_by, tim_sort_by_key};
+        use super::sawtooth;
+        use crate::test_util::random_below;
+
+        #[test]
+        fn test_descending() {
+            let mut input = [2, 1, 10, 4, 4, 3, 7, 5];
+
+            tim_sort_by(&mut input, |a, b| b.cmp(a));
+
+            assert_eq!(&[10, 7, 5, 4, 4, 3, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_stability() {
+            for keys in [
+                random_below(3000, 10, 3),
+                sawtooth(3000, 13),
+                random_below(20, 3, 4),
+            ] {
+                let mut input: Vec<(u64, usize)> = keys.into_iter().zip(0..).collect();
+                let mut expected = input.clone();
+                expected.sort_by_key(|p| p.0);
+
+                tim_sort_by(&mut input, |a, b| a.0.cmp(&b.0));
+
+                assert_eq!(expected, input);
+            }
+        }
+
+        #[test]
+        fn test_stability_by_key() {
+            let mut input: Vec<(u64, usize)> =
+                random_below(3000, 10, 5).into_iter().zip(0..).collect();
+            let mut expected = input.clone();
+            expected.sort_by_key(|p| std::cmp::Reverse(p.0));
+
+            tim_sort_by_key(&mut input, |p| std::cmp::Reverse(p.0));
+
+            assert_eq!(expected, input);
+        }
+    }
+
+    mod test_helpers {
+        use super::super::{
+            binary_insertion_sort, count_run_and_make_ascending, gallop, min_run_length,
+        };
+
+        #[test]
+        fn test_min_run_length() {
+            assert_eq!(0, min_run_length(0));
+            assert_eq!(31, min_run_length(31));
+            assert_eq!(16, min_run_length(32));
+            assert_eq!(17, min_run_length(33));
+            assert_eq!(32, min_run_length(63));
+            assert_eq!(16, min_run_length(1 << 20));
+            assert_eq!(17, min_run_length((1 << 20) + 1));
+        }
+
+        #[test]
+        fn test_count_run() {
+            let mut input = [1, 2, 2, 3, 1];
+            assert_eq!(4, count_run_and_make_ascending(&mut input, &mut i32::cmp));
+
+            let mut input = [5, 4, 2, 2, 1];
+            assert_eq!(3, count_run_and_make_ascending(&mut input, &mut i32::cmp));
+            assert_eq!(&[2, 4, 5, 2, 1], &input);
+        }
+
+        #[test]
+        fn test_binary_insertion_sort() {
+            let mut input = [(1, 'a'), (3, 'a'), (2, 'b'), (1, 'b'), (3, 'b')];
+
+            binary_insertion_sort(&mut input, 2, &mut |a, b| a.0.cmp(&b.0));
+
+            assert_eq!(&[(1, 'a'), (1, 'b'), (2, 'b'), (3, 'a'), (3, 'b')], &input);
+        }
+
+        #[test]
+        fn test_gallop() {
+            let input = [1, 2, 2, 2, 3, 5, 8, 13, 21, 34];
+            for hint in 0..input.len() {
+                assert_eq!(1, gallop(&input, hint, |&x| x < 2));
+                assert_eq!(4, gallop(&input, hint, |&x| x <= 2));
+                assert_eq!(0, gallop(&input, hint, |&x| x < 0));
+                assert_eq!(10, gallop(&input, hint, |&x| x < 100));
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+
+use crate::options::{Input, Options};
+
+// The elements to animate: generated, or read from a file.
+pub fn load(options: &Options) -> Result<Vec<i32>, String> {
+    let len = options.len as i32;
+    match &options.input {
+        Input::Random => Ok(random(options.len, options.seed)),
+        Input::Sorted => Ok((1..=len).collect()),
+        Input::Reversed => Ok((1..=len).rev().collect()),
+        Input::File(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+            parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+    }
+}
+
+// len values between 1 and len, from a linear congruential generator, so the same seed always
+// gives the same input.
+pub fn random(len: usize, seed: u64) -> Vec<i32> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % len as u64) as i32 + 1
+        })
+        .collect()
+}
+
+// Integers separated by whitespace or commas.
+pub fn parse(text: &str) -> Result<Vec<i32>, String> {
+    let mut values = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        for token in line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+        {
+            let value = token
+                .parse()
+                .map_err(|_| format!("line {}: {} is not an integer", number + 1, token))?;
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test_input {
+    use super::{parse, random};
+
+    #[test]
+    fn test_random() {
+        let values = random(100, 1);
+
+        assert_eq!(100, values.len());
+        assert!(values.iter().all(|&v| (1..=100).contains(&v)));
+        assert_eq!(values, random(100, 1));
+        assert_ne!(values, random(100, 2));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(vec![3, -1, 4, 1, 5]), parse("3 -1\n4,1, 5\n\n"));
+        assert_eq!(Ok(vec![]), parse(""));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            Err("line 2: x is not an integer".to_string()),
+            parse("1 2\n3 x")
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+mod input;
+mod options;
+mod recording;
+mod render;
+
+use options::{Command, Options, USAGE};
+use recording::{Recording, State, Summary};
+use render::Chart;
+
+// Clears the screen, and moves the cursor back to the top left to draw the next frame over
+// the previous one.
+const CLEAR: &str = "\x1b[2J";
+const HOME: &str = "\x1b[H";
+// Clears the rest of the line, so a shorter line doesn't leave the end of a longer one.
+const CLEAR_LINE: &str = "\x1b[K";
+
+fn main() {
+    let options = match options::parse(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("visualizer: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("visualizer: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let input = input::load(options)?;
+    let recording = Recording::record(options, &input);
+
+    // Animation only makes sense on a terminal; piped output gets the result only.
+    let stdout = io::stdout();
+    let terminal = stdout.is_terminal();
+    let chart = Chart::new(&input, options.height, terminal);
+    let mut out = stdout.lock();
+
+    let mut state = State::new(&recording);
+    if terminal && options.speed > 0 {
+        let delay = Duration::from_secs_f64(1.0 / options.speed as f64);
+        let mut so_far = Summary::default();
+        write!(out, "{}", CLEAR).map_err(|e| e.to_string())?;
+        for step in recording.steps.iter() {
+            state.apply(step);
+            so_far.count(step);
+
+            let work = recording.uses_work.then_some(state.work.as_slice());
+            let frame = chart.frame(&state.input, work, Some(step));
+            let frame = frame.replace('\n', &format!("{}\n", CLEAR_LINE));
+            write!(
+                out,
+                "{}{}{}{}",
+                HOME,
+                frame,
+                progress(&so_far, recording.steps.len()),
+                CLEAR_LINE
+            )
+            .map_err(|e| e.to_string())?;
+            out.flush().map_err(|e| e.to_string())?;
+            thread::sleep(delay);
+        }
+        writeln!(out).map_err(|e| e.to_string())?;
+    } else {
+        for step in recording.steps.iter() {
+            state.apply(step);
+        }
+        let work = recording.uses_work.then_some(state.work.as_slice());
+        let frame = chart.frame(&state.input, work, None);
+        write!(out, "{}", frame).map_err(|e| e.to_string())?;
+    }
+
+    write!(out, "{}", summary(options, &input, &recording.summary())).map_err(|e| e.to_string())
+}
+
+fn progress(so_far: &Summary, steps: usize) -> String {
+    format!(
+        "step {}/{}  comparisons {}  moves {}",
+        so_far.steps, steps, so_far.comparisons, so_far.moves
+    )
+}
+
+fn summary(options: &Options, input: &[i32], summary: &Summary) -> String {
+    format!(
+        "\n{} of {} elements\n  steps        {}\n  comparisons  {}\n  moves        {}\n",
+        options.algorithm.description(),
+        input.len(),
+        summary.steps,
+        summary.comparisons,
+        summary.moves
+    )
+}
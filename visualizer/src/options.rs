@@ -0,0 +1,228 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: visualizer <algorithm> [options]
+
+Animates a sorting or rotation algorithm in the terminal as bar charts.
+
+Algorithms:
+  top-down           top-down merge sort
+  bottom-up          bottom-up merge sort
+  natural            natural merge sort
+  in-place           merge sort that merges by rotating, without a work buffer
+  rotate             rotation by shifting one place at a time
+  rotate-efficient   rotation by three reversals
+
+The parallel merge sort is not available: its tasks run at the same time, so their steps have
+no single order to animate.
+
+Options:
+  --input <kind>     random (default), sorted or reversed
+  --file <path>      read whitespace or comma separated integers instead
+  --len <n>          number of elements to generate (default 32)
+  --seed <n>         seed for the random input (default 1)
+  --cutoff <n>       longest run the buffered merge sorts sort without splitting (default 1)
+  --factor <n>       places to rotate by, negative to the left (default len / 3)
+  --speed <n>        steps per second (default 20); 0 only shows the result
+  --height <n>       rows of the bar charts (default 16)
+  --help             show this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    TopDown,
+    BottomUp,
+    Natural,
+    InPlace,
+    Rotate,
+    RotateEfficient,
+}
+
+impl Algorithm {
+    pub fn parse(name: &str) -> Option<Algorithm> {
+        match name {
+            "top-down" => Some(Algorithm::TopDown),
+            "bottom-up" => Some(Algorithm::BottomUp),
+            "natural" => Some(Algorithm::Natural),
+            "in-place" => Some(Algorithm::InPlace),
+            "rotate" => Some(Algorithm::Rotate),
+            "rotate-efficient" => Some(Algorithm::RotateEfficient),
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Algorithm::TopDown => "top-down merge sort",
+            Algorithm::BottomUp => "bottom-up merge sort",
+            Algorithm::Natural => "natural merge sort",
+            Algorithm::InPlace => "in-place merge sort",
+            Algorithm::Rotate => "rotate",
+            Algorithm::RotateEfficient => "rotate_efficient",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Random,
+    Sorted,
+    Reversed,
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub algorithm: Algorithm,
+    pub input: Input,
+    pub len: usize,
+    pub seed: u64,
+    pub cutoff: usize,
+    // None rotates by a third of the length.
+    pub factor: Option<i32>,
+    pub speed: u32,
+    pub height: usize,
+}
+
+// What the command line asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+// Parses the arguments after the program name.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut algorithm = None;
+    let mut options = Options {
+        algorithm: Algorithm::TopDown,
+        input: Input::Random,
+        len: 32,
+        seed: 1,
+        cutoff: 1,
+        factor: None,
+        speed: 20,
+        height: 16,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--input" => {
+                options.input = match value("--input")?.as_str() {
+                    "random" => Input::Random,
+                    "sorted" => Input::Sorted,
+                    "reversed" => Input::Reversed,
+                    other => return Err(format!("unknown input {}", other)),
+                }
+            }
+            "--file" => options.input = Input::File(PathBuf::from(value("--file")?)),
+            "--len" => options.len = number("--len", &value("--len")?)?,
+            "--seed" => options.seed = number("--seed", &value("--seed")?)?,
+            "--cutoff" => options.cutoff = number("--cutoff", &value("--cutoff")?)?,
+            "--factor" => options.factor = Some(number("--factor", &value("--factor")?)?),
+            "--speed" => options.speed = number("--speed", &value("--speed")?)?,
+            "--height" => options.height = number("--height", &value("--height")?)?,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            name if algorithm.is_none() => {
+                algorithm = Some(
+                    Algorithm::parse(name).ok_or_else(|| format!("unknown algorithm {}", name))?,
+                )
+            }
+            extra => return Err(format!("unexpected argument {}", extra)),
+        }
+    }
+
+    options.algorithm = algorithm.ok_or("no algorithm given")?;
+    if options.height == 0 {
+        return Err("--height must be at least 1".to_string());
+    }
+    Ok(Command::Run(options))
+}
+
+fn number<N: std::str::FromStr>(name: &str, value: &str) -> Result<N, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+#[cfg(test)]
+mod test_options {
+    use super::{parse, Algorithm, Command, Input, Options};
+    use std::path::PathBuf;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn options(line: &str) -> Options {
+        match parse(args(line)) {
+            Ok(Command::Run(options)) => options,
+            other => panic!("{}: {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = options("top-down");
+
+        assert_eq!(Algorithm::TopDown, options.algorithm);
+        assert_eq!(Input::Random, options.input);
+        assert_eq!(32, options.len);
+        assert_eq!(1, options.cutoff);
+        assert_eq!(None, options.factor);
+        assert_eq!(20, options.speed);
+    }
+
+    #[test]
+    fn test_all_options() {
+        let options = options(
+            "--input reversed --len 10 rotate-efficient --seed 7 --cutoff 4 --factor -3 \
+             --speed 0 --height 5",
+        );
+
+        assert_eq!(
+            Options {
+                algorithm: Algorithm::RotateEfficient,
+                input: Input::Reversed,
+                len: 10,
+                seed: 7,
+                cutoff: 4,
+                factor: Some(-3),
+                speed: 0,
+                height: 5,
+            },
+            options
+        );
+    }
+
+    #[test]
+    fn test_file() {
+        assert_eq!(
+            Input::File(PathBuf::from("data.txt")),
+            options("natural --file data.txt").input
+        );
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(Ok(Command::Help), parse(args("rotate --help")));
+    }
+
+    #[test]
+    fn test_errors() {
+        for (line, error) in [
+            ("", "no algorithm given"),
+            ("quick", "unknown algorithm quick"),
+            ("top-down bottom-up", "unexpected argument bottom-up"),
+            ("top-down --len", "--len needs a value"),
+            ("top-down --len ten", "--len expects a number, got ten"),
+            ("top-down --input shuffled", "unknown input shuffled"),
+            ("top-down --colour", "unknown option --colour"),
+            ("top-down --height 0", "--height must be at least 1"),
+        ] {
+            assert_eq!(Err(error.to_string()), parse(args(line)), "{}", line);
+        }
+    }
+}
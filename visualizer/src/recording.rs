@@ -0,0 +1,246 @@
+use array_rotate::{trace_rotate, trace_rotate_efficient};
+use sorting::merge_sort::{
+    trace_in_place_merge_sort, trace_natural_merge_sort, Buffer, MergeSorter, Trace, TraceEvent,
+};
+
+use crate::options::{Algorithm, Options};
+
+// One step of the animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    // A step of a traced merge sort.
+    Sort(TraceEvent<i32>),
+    // The rotation swapped two elements of the input.
+    Swap(usize, usize),
+}
+
+// Everything the algorithm did to its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub input: Vec<i32>,
+    pub steps: Vec<Step>,
+    // Whether the algorithm moves elements through a work buffer.
+    pub uses_work: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub steps: usize,
+    pub comparisons: usize,
+    // Elements written; a swap writes two.
+    pub moves: usize,
+}
+
+impl Recording {
+    // Runs the algorithm on a copy of input and records it.
+    pub fn record(options: &Options, input: &[i32]) -> Recording {
+        let mut data = input.to_vec();
+        let mut work = input.to_vec();
+        let mut sorter = MergeSorter::new().insertion_cutoff(options.cutoff);
+        let factor = options.factor.unwrap_or(input.len() as i32 / 3);
+
+        let trace = |trace: Trace<i32>, uses_work| Recording {
+            input: input.to_vec(),
+            steps: trace.events().iter().map(|&e| Step::Sort(e)).collect(),
+            uses_work,
+        };
+        let swaps = |swaps: Vec<(usize, usize)>| Recording {
+            input: input.to_vec(),
+            steps: swaps.into_iter().map(|(i, j)| Step::Swap(i, j)).collect(),
+            uses_work: false,
+        };
+        match options.algorithm {
+            Algorithm::TopDown => trace(sorter.trace(&mut data), true),
+            Algorithm::BottomUp => trace(sorter.trace_bottom_up(&mut data), true),
+            Algorithm::Natural => trace(trace_natural_merge_sort(&mut data, &mut work), true),
+            Algorithm::InPlace => trace(trace_in_place_merge_sort(&mut data), false),
+            Algorithm::Rotate => swaps(trace_rotate(&mut data, factor)),
+            Algorithm::RotateEfficient => swaps(trace_rotate_efficient(&mut data, factor)),
+        }
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        for step in self.steps.iter() {
+            summary.count(step);
+        }
+        summary
+    }
+}
+
+impl Summary {
+    pub fn count(&mut self, step: &Step) {
+        self.steps += 1;
+        match step {
+            Step::Sort(TraceEvent::Compare { .. }) => self.comparisons += 1,
+            Step::Sort(TraceEvent::Write(_)) => self.moves += 1,
+            Step::Swap(..) => self.moves += 2,
+            Step::Sort(_) => {}
+        }
+    }
+}
+
+// The input and work buffers part way through a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub input: Vec<i32>,
+    pub work: Vec<i32>,
+}
+
+impl State {
+    // The work buffer starts out as a copy of the input, as Recording::record passes it.
+    pub fn new(recording: &Recording) -> State {
+        State {
+            input: recording.input.clone(),
+            work: recording.input.clone(),
+        }
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        match *step {
+            Step::Sort(TraceEvent::Write(slot)) => {
+                self.buffer_mut(slot.buffer)[slot.index] = slot.value;
+            }
+            Step::Swap(i, j) => self.input.swap(i, j),
+            Step::Sort(_) => {}
+        }
+    }
+
+    fn buffer_mut(&mut self, buffer: Buffer) -> &mut [i32] {
+        match buffer {
+            Buffer::Input => &mut self.input,
+            Buffer::Work => &mut self.work,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_recording {
+    use sorting::merge_sort::TraceEvent;
+
+    use super::{Recording, State, Step, Summary};
+    use crate::input::random;
+    use crate::options::{Algorithm, Input, Options};
+
+    fn options(algorithm: Algorithm) -> Options {
+        Options {
+            algorithm,
+            input: Input::Random,
+            len: 40,
+            seed: 1,
+            cutoff: 1,
+            factor: None,
+            speed: 0,
+            height: 16,
+        }
+    }
+
+    fn replay(recording: &Recording) -> Vec<i32> {
+        let mut state = State::new(recording);
+        for step in recording.steps.iter() {
+            state.apply(step);
+        }
+        state.input
+    }
+
+    #[test]
+    fn test_merge_sorts_replay_to_sorted() {
+        let input = random(40, 1);
+        let mut expected = input.clone();
+        expected.sort();
+
+        for algorithm in [Algorithm::TopDown, Algorithm::BottomUp, Algorithm::Natural] {
+            let recording = Recording::record(&options(algorithm), &input);
+
+            assert!(recording.uses_work);
+            assert_eq!(input, recording.input);
+            assert_eq!(expected, replay(&recording), "{:?}", algorithm);
+            assert!(recording.summary().comparisons > 0, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_in_place_replays_to_sorted() {
+        let input = random(40, 1);
+        let mut expected = input.clone();
+        expected.sort();
+
+        let recording = Recording::record(&options(Algorithm::InPlace), &input);
+
+        assert!(!recording.uses_work);
+        assert_eq!(expected, replay(&recording));
+        assert!(recording.summary().comparisons > 0);
+    }
+
+    #[test]
+    fn test_rotations_replay() {
+        let input: Vec<i32> = (1..=9).collect();
+
+        for algorithm in [Algorithm::Rotate, Algorithm::RotateEfficient] {
+            let mut options = options(algorithm);
+            options.factor = Some(2);
+
+            let recording = Recording::record(&options, &input);
+
+            assert!(!recording.uses_work);
+            assert_eq!(vec![8, 9, 1, 2, 3, 4, 5, 6, 7], replay(&recording));
+        }
+    }
+
+    #[test]
+    fn test_default_factor() {
+        let input: Vec<i32> = (1..=9).collect();
+
+        let recording = Recording::record(&options(Algorithm::RotateEfficient), &input);
+
+        assert_eq!(vec![7, 8, 9, 1, 2, 3, 4, 5, 6], replay(&recording));
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut options = options(Algorithm::Rotate);
+        options.factor = Some(1);
+
+        let recording = Recording::record(&options, &[1, 2, 3, 4]);
+
+        assert_eq!(
+            vec![Step::Swap(2, 3), Step::Swap(1, 2), Step::Swap(0, 1)],
+            recording.steps
+        );
+        assert_eq!(
+            Summary {
+                steps: 3,
+                comparisons: 0,
+                moves: 6
+            },
+            recording.summary()
+        );
+    }
+
+    #[test]
+    fn test_cutoff() {
+        let input = random(40, 3);
+        let mut options = options(Algorithm::TopDown);
+
+        let split = Recording::record(&options, &input);
+        options.cutoff = 40;
+        let direct = Recording::record(&options, &input);
+
+        // With the cutoff at the length the input is sorted as a single run.
+        let splits_and_merges = |recording: &Recording| {
+            recording
+                .steps
+                .iter()
+                .filter(|step| {
+                    matches!(
+                        step,
+                        Step::Sort(TraceEvent::Split { .. } | TraceEvent::Merge { .. })
+                    )
+                })
+                .count()
+        };
+        assert_eq!(39 + 39, splits_and_merges(&split));
+        assert_eq!(0, splits_and_merges(&direct));
+        assert_eq!(replay(&split), replay(&direct));
+    }
+}
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use sorting::merge_sort::{Buffer, Slot, TraceEvent};
+
+use crate::recording::Step;
+
+const COMPARE_COLOR: &str = "\x1b[33m";
+const WRITE_COLOR: &str = "\x1b[32m";
+const RANGE_COLOR: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+// Draws buffers as columns of bars, scaled so that the smallest value of the input is one row
+// high and the largest fills the chart.
+pub struct Chart {
+    min: i32,
+    max: i32,
+    height: usize,
+    color: bool,
+}
+
+impl Chart {
+    pub fn new(values: &[i32], height: usize, color: bool) -> Chart {
+        Chart {
+            min: values.iter().copied().min().unwrap_or(0),
+            max: values.iter().copied().max().unwrap_or(0),
+            height,
+            color,
+        }
+    }
+
+    // Draws the input and, if there is one, the work buffer, with the elements the step
+    // touches highlighted, followed by the caption.
+    pub fn frame(&self, input: &[i32], work: Option<&[i32]>, step: Option<&Step>) -> String {
+        let mut out = String::new();
+        self.bars(&mut out, "input", input, Buffer::Input, step);
+        if let Some(work) = work {
+            self.bars(&mut out, "work", work, Buffer::Work, step);
+        }
+        if let Some(step) = step {
+            out.push_str(&describe(step));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn bars(
+        &self,
+        out: &mut String,
+        label: &str,
+        values: &[i32],
+        buffer: Buffer,
+        step: Option<&Step>,
+    ) {
+        // Wide bars for short inputs, one column per element for long ones.
+        let (bar, blank, gap) = match values.len() {
+            0..=40 => ("██", "  ", " "),
+            41..=80 => ("█", " ", " "),
+            _ => ("█", " ", ""),
+        };
+        let colors: Vec<Option<&str>> = (0..values.len())
+            .map(|i| step.and_then(|step| highlight(step, buffer, i)))
+            .collect();
+
+        out.push_str(label);
+        out.push('\n');
+        for row in (1..=self.height).rev() {
+            let mut line = String::new();
+            for (i, &value) in values.iter().enumerate() {
+                let filled = self.rows(value) >= row;
+                let cell = if filled { bar } else { blank };
+                match colors[i] {
+                    Some(color) if self.color && filled => {
+                        let _ = write!(line, "{}{}{}{}", color, cell, RESET, gap);
+                    }
+                    _ => {
+                        line.push_str(cell);
+                        line.push_str(gap);
+                    }
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+
+    // How many rows tall the bar for value is, from 1 to height.
+    fn rows(&self, value: i32) -> usize {
+        if self.max == self.min {
+            return self.height;
+        }
+        let above_min = (value as i64 - self.min as i64) as usize;
+        let range = (self.max as i64 - self.min as i64) as usize;
+        1 + above_min * (self.height - 1) / range
+    }
+}
+
+// The color for element index of buffer during step, if the step touches it.
+fn highlight(step: &Step, buffer: Buffer, index: usize) -> Option<&'static str> {
+    let touches = |slot: &Slot<i32>| slot.buffer == buffer && slot.index == index;
+    match step {
+        Step::Sort(TraceEvent::Compare { left, right, .. }) if touches(left) || touches(right) => {
+            Some(COMPARE_COLOR)
+        }
+        Step::Sort(TraceEvent::Write(slot)) if touches(slot) => Some(WRITE_COLOR),
+        Step::Sort(TraceEvent::Merge {
+            from, start, end, ..
+        }) if *from == buffer && (*start..*end).contains(&index) => Some(RANGE_COLOR),
+        Step::Swap(i, j) if buffer == Buffer::Input && (index == *i || index == *j) => {
+            Some(WRITE_COLOR)
+        }
+        _ => None,
+    }
+}
+
+fn name(buffer: Buffer) -> &'static str {
+    match buffer {
+        Buffer::Input => "input",
+        Buffer::Work => "work",
+    }
+}
+
+// One line saying what the step does.
+pub fn describe(step: &Step) -> String {
+    match *step {
+        Step::Sort(TraceEvent::Split { start, middle, end }) => format!(
+            "split {}..{} into {}..{} and {}..{}",
+            start, end, start, middle, middle, end
+        ),
+        Step::Sort(TraceEvent::Merge {
+            from,
+            start,
+            middle,
+            end,
+        }) => {
+            let to = match from {
+                Buffer::Input => Buffer::Work,
+                Buffer::Work => Buffer::Input,
+            };
+            format!(
+                "merge {}[{}..{}] and {}[{}..{}] into {}[{}..{}]",
+                name(from),
+                start,
+                middle,
+                name(from),
+                middle,
+                end,
+                name(to),
+                start,
+                end
+            )
+        }
+        Step::Sort(TraceEvent::Compare {
+            left,
+            right,
+            result,
+        }) => format!(
+            "compare {}[{}] = {} with {}[{}] = {}: {}",
+            name(left.buffer),
+            left.index,
+            left.value,
+            name(right.buffer),
+            right.index,
+            right.value,
+            match result {
+                Ordering::Less => "less",
+                Ordering::Equal => "equal",
+                Ordering::Greater => "greater",
+            }
+        ),
+        Step::Sort(TraceEvent::Write(slot)) => format!(
+            "write {}[{}] = {}",
+            name(slot.buffer),
+            slot.index,
+            slot.value
+        ),
+        Step::Swap(i, j) => format!("swap input[{}] and input[{}]", i, j),
+    }
+}
+
+#[cfg(test)]
+mod test_render {
+    use std::cmp::Ordering;
+
+    use sorting::merge_sort::{Buffer, Slot, TraceEvent};
+
+    use super::{describe, Chart};
+    use crate::recording::Step;
+
+    #[test]
+    fn test_bars() {
+        let chart = Chart::new(&[1, 2, 3], 3, false);
+
+        let frame = chart.frame(&[3, 1, 2], None, None);
+
+        assert_eq!("input\n██\n██    ██\n██ ██ ██\n", frame);
+    }
+
+    #[test]
+    fn test_work_buffer_and_caption() {
+        let chart = Chart::new(&[5, 5], 2, false);
+
+        let frame = chart.frame(&[5, 5], Some(&[5, 5]), Some(&Step::Swap(0, 1)));
+
+        assert_eq!(
+            "input\n██ ██\n██ ██\nwork\n██ ██\n██ ██\nswap input[0] and input[1]\n",
+            frame
+        );
+    }
+
+    #[test]
+    fn test_highlights() {
+        let chart = Chart::new(&[1, 2], 1, true);
+        let step = Step::Sort(TraceEvent::Write(Slot {
+            buffer: Buffer::Work,
+            index: 1,
+            value: 2,
+        }));
+
+        let frame = chart.frame(&[1, 2], Some(&[1, 2]), Some(&step));
+
+        assert_eq!(
+            "input\n██ ██\nwork\n██ \x1b[32m██\x1b[0m\nwrite work[1] = 2\n",
+            frame
+        );
+    }
+
+    #[test]
+    fn test_long_input() {
+        let values: Vec<i32> = (0..100).collect();
+        let chart = Chart::new(&values, 1, false);
+
+        let frame = chart.frame(&values, None, None);
+
+        assert_eq!(format!("input\n{}\n", "█".repeat(100)), frame);
+    }
+
+    #[test]
+    fn test_describe() {
+        let slot = |buffer, index, value| Slot {
+            buffer,
+            index,
+            value,
+        };
+
+        assert_eq!(
+            "split 0..8 into 0..4 and 4..8",
+            describe(&Step::Sort(TraceEvent::Split {
+                start: 0,
+                middle: 4,
+                end: 8
+            }))
+        );
+        assert_eq!(
+            "merge work[0..4] and work[4..8] into input[0..8]",
+            describe(&Step::Sort(TraceEvent::Merge {
+                from: Buffer::Work,
+                start: 0,
+                middle: 4,
+                end: 8
+            }))
+        );
+        assert_eq!(
+            "compare work[0] = 7 with input[3] = 2: greater",
+            describe(&Step::Sort(TraceEvent::Compare {
+                left: slot(Buffer::Work, 0, 7),
+                right: slot(Buffer::Input, 3, 2),
+                result: Ordering::Greater
+            }))
+        );
+        assert_eq!(
+            "write input[5] = -1",
+            describe(&Step::Sort(TraceEvent::Write(slot(Buffer::Input, 5, -1))))
+        );
+    }
+}